@@ -0,0 +1,23 @@
+fn main() {
+    // Заголовок для C API создаётся только при сборке с feature capi и только в OUT_DIR,
+    // чтобы обычная сборка не меняла исходники. Заголовок include/kavn.h в репозитории
+    // обновляется явно: KAVN_UPDATE_HEADER=1 cargo build --features capi
+    #[cfg(feature = "capi")]
+    {
+        let crate_dir = std::env::var("CARGO_MANIFEST_DIR").unwrap();
+        let header = format!("{}/kavn.h", std::env::var("OUT_DIR").unwrap());
+
+        println!("cargo:rerun-if-changed=src/capi.rs");
+        println!("cargo:rerun-if-changed=cbindgen.toml");
+        println!("cargo:rerun-if-env-changed=KAVN_UPDATE_HEADER");
+
+        cbindgen::generate(&crate_dir)
+            .expect("не удалось создать заголовок C API")
+            .write_to_file(&header);
+
+        if std::env::var_os("KAVN_UPDATE_HEADER").is_some() {
+            std::fs::copy(&header, format!("{}/include/kavn.h", crate_dir))
+                .expect("не удалось обновить include/kavn.h");
+        }
+    }
+}
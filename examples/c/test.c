@@ -0,0 +1,77 @@
+/*
+ * Проверка C API. Сборка и запуск из корня репозитория:
+ *
+ *     cargo build --release --features capi
+ *     cc examples/c/test.c -Iinclude -Ltarget/release -lkavn -o kavn_test
+ *     LD_LIBRARY_PATH=target/release ./kavn_test
+ */
+
+#include <stdio.h>
+#include <stdlib.h>
+
+#include "kavn.h"
+
+#define CHECK(condition)                                                      \
+    do {                                                                      \
+        if (!(condition)) {                                                   \
+            fprintf(stderr, "%s:%d: %s\n", __FILE__, __LINE__, #condition);   \
+            exit(1);                                                          \
+        }                                                                     \
+    } while (0)
+
+/* Номера состояний: 13 - обычное передающее вправо, 17 - оно же с возбуждением */
+#define T000 13
+#define T001 17
+
+int main(void) {
+    CHECK(kavn_api_version() == KAVN_API_VERSION);
+
+    KavnField *field = kavn_field_new();
+    CHECK(field != NULL);
+    CHECK(kavn_population(field) == 0);
+
+    /* Провод из 10 клеток с возбуждённой клеткой в начале */
+    for (int64_t x = 0; x < 10; x++) {
+        CHECK(kavn_set_cell(field, x, 0, T000) == KAVN_STATUS_OK);
+    }
+    CHECK(kavn_set_cell(field, 0, 0, T001) == KAVN_STATUS_OK);
+
+    CHECK(kavn_get_cell(field, 0, 0) == T001);
+    CHECK(kavn_get_cell(field, 1000, 1000) == 0);
+    CHECK(kavn_population(field) == 10);
+
+    /* Сигнал проходит по проводу на одну клетку за такт */
+    CHECK(kavn_step(field, 3) == KAVN_STATUS_OK);
+    CHECK(kavn_tact(field) == 3);
+    CHECK(kavn_get_cell(field, 3, 0) == T001);
+
+    int64_t x0, y0, x1, y1;
+    CHECK(kavn_bounds(field, &x0, &y0, &x1, &y1));
+    CHECK(x0 == 0 && y0 == 0 && x1 == 9 && y1 == 0);
+
+    /* Ошибки */
+    CHECK(kavn_set_cell(field, 0, 0, 29) == KAVN_STATUS_INVALID_STATE);
+    printf("Ожидаемая ошибка: %s\n", kavn_last_error());
+    CHECK(kavn_set_cell(NULL, 0, 0, 0) == KAVN_STATUS_NULL_POINTER);
+    CHECK(kavn_field_load("нет такого файла.data") == NULL);
+    printf("Ожидаемая ошибка: %s\n", kavn_last_error());
+
+    /* Сохранение и загрузка */
+    CHECK(kavn_field_save(field, "kavn_test.rle") == KAVN_STATUS_OK);
+
+    KavnField *loaded = kavn_field_load("kavn_test.rle");
+    CHECK(loaded != NULL);
+    CHECK(kavn_tact(loaded) == 3);
+    CHECK(kavn_population(loaded) == 10);
+    CHECK(kavn_get_cell(loaded, 3, 0) == T001);
+
+    remove("kavn_test.rle");
+
+    kavn_field_free(loaded);
+    kavn_field_free(field);
+    kavn_field_free(NULL);
+
+    printf("Все проверки пройдены\n");
+
+    return 0;
+}
@@ -0,0 +1,46 @@
+#ifndef KAVN_H
+#define KAVN_H
+
+/* Файл создан cbindgen (KAVN_UPDATE_HEADER=1 cargo build --features capi), не редактируйте его вручную */
+
+#include <stdbool.h>
+#include <stdint.h>
+
+#define KAVN_API_VERSION 1
+
+typedef enum KavnStatus {
+  KAVN_STATUS_OK = 0,
+  KAVN_STATUS_NULL_POINTER = 1,
+  KAVN_STATUS_INVALID_STATE = 2,
+  KAVN_STATUS_INVALID_PATH = 3,
+  KAVN_STATUS_FILE_ERROR = 4,
+  KAVN_STATUS_PANIC = 5,
+} KavnStatus;
+
+typedef struct KavnField KavnField;
+
+uint32_t kavn_api_version(void);
+
+const char *kavn_last_error(void);
+
+struct KavnField *kavn_field_new(void);
+
+void kavn_field_free(struct KavnField *field);
+
+struct KavnField *kavn_field_load(const char *path);
+
+enum KavnStatus kavn_field_save(const struct KavnField *field, const char *path);
+
+int32_t kavn_get_cell(const struct KavnField *field, int64_t x, int64_t y);
+
+enum KavnStatus kavn_set_cell(struct KavnField *field, int64_t x, int64_t y, uint8_t state);
+
+enum KavnStatus kavn_step(struct KavnField *field, uint64_t tacts);
+
+uint64_t kavn_tact(const struct KavnField *field);
+
+uint64_t kavn_population(const struct KavnField *field);
+
+bool kavn_bounds(const struct KavnField *field, int64_t *x0, int64_t *y0, int64_t *x1, int64_t *y1);
+
+#endif  /* KAVN_H */
@@ -0,0 +1,263 @@
+use crate::cellular_automaton::{CellularAutomation, State};
+use crate::files::{self, Metadata};
+use std::cell::RefCell;
+use std::ffi::{c_char, CStr, CString};
+use std::panic::{self, AssertUnwindSafe};
+use std::path::Path;
+use std::ptr;
+
+// C API ядра (заголовок include/kavn.h создаётся cbindgen, см. build.rs).
+// Состояния клеток передаются номерами от 0 до 28. Функции, которые могут завершиться
+// ошибкой, возвращают KavnStatus, а текст последней ошибки потока можно получить
+// через kavn_last_error.
+//
+// Указатели на поле должны быть получены из kavn_field_new или kavn_field_load и не
+// использоваться после kavn_field_free, пути - строки в UTF-8, оканчивающиеся нулём.
+// NULL вместо поля или пути допустим и приводит к ошибке KAVN_STATUS_NULL_POINTER.
+// Внутренняя ошибка (паника) не выходит за пределы библиотеки: функция возвращает
+// KAVN_STATUS_PANIC, NULL или то же, что и при NULL вместо поля.
+
+// Версия API, меняется при несовместимых изменениях
+pub const KAVN_API_VERSION: u32 = 1;
+
+#[repr(C)]
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum KavnStatus {
+    Ok = 0,
+    NullPointer = 1,
+    InvalidState = 2,
+    InvalidPath = 3,
+    FileError = 4,
+    Panic = 5,
+}
+
+// Поле вместе с описанием, чтобы при сохранении оно не терялось
+pub struct KavnField {
+    automaton: CellularAutomation,
+    metadata: Metadata,
+}
+
+thread_local! {
+    static LAST_ERROR: RefCell<CString> = RefCell::new(CString::default());
+}
+
+fn fail(status: KavnStatus, message: String) -> KavnStatus {
+    let message = CString::new(message.replace('\0', " ")).unwrap_or_default();
+
+    LAST_ERROR.with(|error| *error.borrow_mut() = message);
+
+    status
+}
+
+// Паника не должна выходить за границу extern "C", поэтому она перехватывается:
+// функция возвращает on_panic, а текст паники доступен через kavn_last_error
+fn guard<T>(on_panic: T, f: impl FnOnce() -> T) -> T {
+    panic::catch_unwind(AssertUnwindSafe(f)).unwrap_or_else(|payload| {
+        let message = payload
+            .downcast_ref::<&str>()
+            .map(|message| message.to_string())
+            .or_else(|| payload.downcast_ref::<String>().cloned())
+            .unwrap_or_default();
+
+        fail(
+            KavnStatus::Panic,
+            format!("внутренняя ошибка kavn: {}", message),
+        );
+
+        on_panic
+    })
+}
+
+unsafe fn path_from<'a>(path: *const c_char) -> Result<&'a Path, KavnStatus> {
+    if path.is_null() {
+        return Err(fail(KavnStatus::NullPointer, "путь не указан".to_string()));
+    }
+
+    CStr::from_ptr(path)
+        .to_str()
+        .map(Path::new)
+        .map_err(|_| fail(KavnStatus::InvalidPath, "путь не в UTF-8".to_string()))
+}
+
+#[no_mangle]
+pub extern "C" fn kavn_api_version() -> u32 {
+    KAVN_API_VERSION
+}
+
+// Текст последней ошибки в текущем потоке. Строка действительна до следующего вызова API.
+#[no_mangle]
+pub extern "C" fn kavn_last_error() -> *const c_char {
+    LAST_ERROR.with(|error| error.borrow().as_ptr())
+}
+
+#[no_mangle]
+pub extern "C" fn kavn_field_new() -> *mut KavnField {
+    guard(ptr::null_mut(), || {
+        Box::into_raw(Box::new(KavnField {
+            automaton: CellularAutomation::new(),
+            metadata: Metadata::default(),
+        }))
+    })
+}
+
+#[no_mangle]
+pub unsafe extern "C" fn kavn_field_free(field: *mut KavnField) {
+    guard((), || {
+        if !field.is_null() {
+            drop(Box::from_raw(field));
+        }
+    })
+}
+
+// Загрузка поля из файла (формат по расширению). При ошибке возвращает NULL.
+#[no_mangle]
+pub unsafe extern "C" fn kavn_field_load(path: *const c_char) -> *mut KavnField {
+    guard(ptr::null_mut(), || {
+        let path = match path_from(path) {
+            Ok(path) => path,
+            Err(_) => return ptr::null_mut(),
+        };
+
+        match files::load(path) {
+            Ok(document) => Box::into_raw(Box::new(KavnField {
+                automaton: document.automaton,
+                metadata: document.metadata,
+            })),
+            Err(err) => {
+                fail(KavnStatus::FileError, err.to_string());
+
+                ptr::null_mut()
+            }
+        }
+    })
+}
+
+#[no_mangle]
+pub unsafe extern "C" fn kavn_field_save(
+    field: *const KavnField,
+    path: *const c_char,
+) -> KavnStatus {
+    guard(KavnStatus::Panic, || {
+        let Some(field) = field.as_ref() else {
+            return fail(KavnStatus::NullPointer, "поле не указано".to_string());
+        };
+
+        let path = match path_from(path) {
+            Ok(path) => path,
+            Err(status) => return status,
+        };
+
+        match files::save(path, &field.automaton, &field.metadata) {
+            Ok(()) => KavnStatus::Ok,
+            Err(err) => fail(KavnStatus::FileError, err.to_string()),
+        }
+    })
+}
+
+// Номер состояния клетки или -1, если поле не указано
+#[no_mangle]
+pub unsafe extern "C" fn kavn_get_cell(field: *const KavnField, x: i64, y: i64) -> i32 {
+    guard(-1, || match field.as_ref() {
+        None => -1,
+        Some(field) => field
+            .automaton
+            .try_get_cell((x, y))
+            .copied()
+            .unwrap_or_default()
+            .index() as i32,
+    })
+}
+
+#[no_mangle]
+pub unsafe extern "C" fn kavn_set_cell(
+    field: *mut KavnField,
+    x: i64,
+    y: i64,
+    state: u8,
+) -> KavnStatus {
+    guard(KavnStatus::Panic, || {
+        let Some(field) = field.as_mut() else {
+            return fail(KavnStatus::NullPointer, "поле не указано".to_string());
+        };
+
+        match State::from_index(state) {
+            Some(state) => {
+                field.automaton.set_cell((x, y), state);
+
+                KavnStatus::Ok
+            }
+            None => fail(
+                KavnStatus::InvalidState,
+                format!("неизвестный номер состояния {}", state),
+            ),
+        }
+    })
+}
+
+#[no_mangle]
+pub unsafe extern "C" fn kavn_step(field: *mut KavnField, tacts: u64) -> KavnStatus {
+    guard(KavnStatus::Panic, || {
+        let Some(field) = field.as_mut() else {
+            return fail(KavnStatus::NullPointer, "поле не указано".to_string());
+        };
+
+        for _ in 0..tacts {
+            field.automaton.tact();
+        }
+
+        KavnStatus::Ok
+    })
+}
+
+// Текущий такт поля (0, если поле не указано)
+#[no_mangle]
+pub unsafe extern "C" fn kavn_tact(field: *const KavnField) -> u64 {
+    guard(0, || field.as_ref().map_or(0, |field| field.automaton.tact))
+}
+
+// Количество непустых клеток (0, если поле не указано)
+#[no_mangle]
+pub unsafe extern "C" fn kavn_population(field: *const KavnField) -> u64 {
+    guard(0, || {
+        field
+            .as_ref()
+            .map_or(0, |field| field.automaton.population() as u64)
+    })
+}
+
+// Углы прямоугольника с непустыми клетками. Возвращает false, если поле пустое или не указано.
+#[no_mangle]
+pub unsafe extern "C" fn kavn_bounds(
+    field: *const KavnField,
+    x0: *mut i64,
+    y0: *mut i64,
+    x1: *mut i64,
+    y1: *mut i64,
+) -> bool {
+    guard(false, || {
+        let bounds = field.as_ref().and_then(|field| field.automaton.bounds());
+
+        match bounds {
+            Some(((bx0, by0), (bx1, by1)))
+                if !(x0.is_null() || y0.is_null() || x1.is_null() || y1.is_null()) =>
+            {
+                (*x0, *y0, *x1, *y1) = (bx0, by0, bx1, by1);
+
+                true
+            }
+            _ => false,
+        }
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    // Заголовок в репозитории совпадает с созданным при этой сборке
+    #[test]
+    fn header_is_up_to_date() {
+        assert!(
+            include_str!(concat!(env!("OUT_DIR"), "/kavn.h")) == include_str!("../include/kavn.h"),
+            "include/kavn.h устарел, обновите его: KAVN_UPDATE_HEADER=1 cargo build --features capi"
+        );
+    }
+}
@@ -0,0 +1,47 @@
+use super::{CellularAutomation, State};
+use std::collections::HashMap;
+
+// Отличие клетки второго поля от той же клетки первого
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Difference {
+    // Клетка была пустой, а стала непустой
+    Added(State),
+    // Клетка была непустой, а стала пустой
+    Removed(State),
+    Changed { from: State, to: State },
+}
+
+// Все различающиеся клетки двух полей, построчно сверху вниз и слева направо.
+// Такт и размеры полей не учитываются.
+pub fn diff(a: &CellularAutomation, b: &CellularAutomation) -> Vec<((i64, i64), Difference)> {
+    let mut old: HashMap<(i64, i64), State> = a
+        .cells()
+        .filter(|(_, state)| **state != State::Unexcitable)
+        .map(|(coords, state)| (coords, *state))
+        .collect();
+
+    let mut differences = vec![];
+
+    for (coords, state) in b.cells() {
+        if *state == State::Unexcitable {
+            continue;
+        }
+
+        match old.remove(&coords) {
+            None => differences.push((coords, Difference::Added(*state))),
+            Some(from) if from != *state => {
+                differences.push((coords, Difference::Changed { from, to: *state }))
+            }
+            Some(_) => {}
+        }
+    }
+
+    differences.extend(
+        old.into_iter()
+            .map(|(coords, state)| (coords, Difference::Removed(state))),
+    );
+
+    differences.sort_by_key(|((x, y), _)| (*y, *x));
+
+    differences
+}
@@ -1,40 +1,87 @@
+mod diff;
 mod states;
+#[cfg(test)]
+pub mod testing;
 use serde::{Deserialize, Serialize};
 
+pub use diff::*;
 pub use states::*;
 use std::collections::vec_deque::VecDeque;
 use std::collections::HashMap;
+use std::fmt::{Display, Formatter};
 
-#[derive(Serialize, Deserialize, Debug)]
+// Прямоугольник клеток: левый верхний и правый нижний углы включительно
+pub type Region = ((i64, i64), (i64, i64));
+
+#[derive(Serialize, Deserialize, Debug, Clone)]
 pub struct CellularAutomation {
     pub field: VecDeque<VecDeque<State>>,
     pub tact: u64,
     pub center: (i64, i64),
 }
 
-impl Clone for CellularAutomation {
-    fn clone(&self) -> Self {
-        let field = {
-            let mut f = VecDeque::new();
-
-            for row in &self.field {
-                let mut c_row = VecDeque::new();
+// Сохранённое состояние поля вместе с его отпечатком на момент сохранения
+#[derive(Debug, Clone)]
+pub struct Snapshot {
+    automaton: CellularAutomation,
+    fingerprint: u64,
+}
 
-                for state in row {
-                    c_row.push_front(state.clone());
-                }
+impl Snapshot {
+    pub fn new(automaton: &CellularAutomation) -> Self {
+        Self {
+            automaton: automaton.clone(),
+            fingerprint: automaton.fingerprint(),
+        }
+    }
 
-                f.push_front(c_row);
-            }
+    pub fn tact(&self) -> u64 {
+        self.automaton.tact
+    }
 
-            f
-        };
+    // Отпечаток взят с исходного поля, а не с копии, поэтому ошибка при копировании
+    // (при сохранении или при восстановлении) приводит к несовпадению
+    pub fn restore(&self) -> Result<CellularAutomation, SnapshotMismatch> {
+        let restored = self.automaton.clone();
 
-        Self {
-            tact: self.tact,
-            center: self.center,
-            field,
+        if restored.fingerprint() != self.fingerprint {
+            return Err(SnapshotMismatch {
+                tact: self.automaton.tact,
+            });
         }
+
+        Ok(restored)
+    }
+}
+
+// Клетки, затронутые тактом
+#[derive(Debug, Default)]
+pub struct Activity {
+    // Изменившие состояние
+    pub changed: Vec<(i64, i64)>,
+
+    // Возбуждённые после такта
+    pub excited: Vec<(i64, i64)>,
+}
+
+#[derive(Debug)]
+pub struct SnapshotMismatch {
+    pub tact: u64,
+}
+
+impl Display for SnapshotMismatch {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        write!(
+            f,
+            "Восстановленное на такте {} поле не совпадает с сохранённым",
+            self.tact
+        )
+    }
+}
+
+impl Default for CellularAutomation {
+    fn default() -> Self {
+        Self::new()
     }
 }
 
@@ -59,10 +106,62 @@ impl CellularAutomation {
         }
     }
 
+    // Создание поля по списку непустых клеток.
+    // Поле сразу выделяется нужного размера, а не расширяется по одной строке/столбцу.
+    pub fn from_cells(cells: &[((i64, i64), State)], tact: u64) -> Self {
+        let ((x0, y0), (x1, y1)) =
+            bounds_of(cells.iter().map(|(coords, _)| *coords)).unwrap_or(((0, 0), (0, 0)));
+
+        // Запас в 2 клетки с каждой стороны, как и при расширении в set_cell
+        let (width, height) = ((x1 - x0 + 5) as usize, (y1 - y0 + 5) as usize);
+
+        let mut ca = Self {
+            field: VecDeque::from(vec![
+                VecDeque::from(vec![State::Unexcitable; width]);
+                height
+            ]),
+            center: (2 - x0, 2 - y0),
+            tact,
+        };
+
+        for &((x, y), state) in cells {
+            ca.field[(ca.center.1 + y) as usize][(ca.center.0 + x) as usize] = state;
+        }
+
+        ca
+    }
+
+    // Прямоугольник, содержащий все непустые клетки
+    pub fn bounds(&self) -> Option<Region> {
+        bounds_of(
+            self.cells()
+                .filter(|(_, state)| **state != State::Unexcitable)
+                .map(|(coords, _)| coords),
+        )
+    }
+
+    // Количество непустых клеток
+    pub fn population(&self) -> usize {
+        self.cells()
+            .filter(|(_, state)| **state != State::Unexcitable)
+            .count()
+    }
+
     pub fn get_cell(&self, coords: (i64, i64)) -> &State {
         &self.field[(self.center.1 + coords.1) as usize][(self.center.0 + coords.0) as usize]
     }
 
+    // Клетка по координатам или None, если поле ещё не расширялось до неё
+    pub fn try_get_cell(&self, coords: (i64, i64)) -> Option<&State> {
+        let (x, y) = (self.center.0 + coords.0, self.center.1 + coords.1);
+
+        if x < 0 || y < 0 {
+            return None;
+        }
+
+        self.field.get(y as usize)?.get(x as usize)
+    }
+
     pub fn set_cell(&mut self, coords: (i64, i64), value: State) {
         while self.center.1 + coords.1 < 2 {
             let mut new_row = VecDeque::new();
@@ -98,10 +197,54 @@ impl CellularAutomation {
             value;
     }
 
+    // Все клетки поля вместе с их координатами (построчно, сверху вниз и слева направо)
+    pub fn cells(&self) -> impl Iterator<Item = ((i64, i64), &State)> {
+        self.field.iter().enumerate().flat_map(move |(y, row)| {
+            row.iter().enumerate().map(move |(x, state)| {
+                ((x as i64 - self.center.0, y as i64 - self.center.1), state)
+            })
+        })
+    }
+
+    // Стабильный хэш содержимого поля (FNV-1a).
+    // Учитываются только непустые клетки и их координаты, поэтому хэш не зависит
+    // от того, насколько поле расширено, а также от текущего такта.
+    pub fn fingerprint(&self) -> u64 {
+        const FNV_OFFSET: u64 = 0xcbf2_9ce4_8422_2325;
+        const FNV_PRIME: u64 = 0x0000_0100_0000_01b3;
+
+        let mut hash = FNV_OFFSET;
+
+        for ((x, y), state) in self.cells() {
+            if *state == State::Unexcitable {
+                continue;
+            }
+
+            for byte in x
+                .to_le_bytes()
+                .into_iter()
+                .chain(y.to_le_bytes())
+                .chain([state.index()])
+            {
+                hash ^= byte as u64;
+                hash = hash.wrapping_mul(FNV_PRIME);
+            }
+        }
+
+        hash
+    }
+
     pub fn tact(&mut self) {
+        self.tact_with_activity();
+    }
+
+    // Такт, после которого возвращаются клетки, изменившие состояние, и возбуждённые клетки.
+    // Возбуждённые клетки собираются в том же обходе поля, что и события.
+    pub fn tact_with_activity(&mut self) -> Activity {
         self.tact += 1;
 
         let mut event_buffer = HashMap::new();
+        let mut activity = Activity::default();
 
         for (y, row) in self.field.iter().enumerate() {
             if y == 0 || y == self.field.len() - 1 {
@@ -113,6 +256,8 @@ impl CellularAutomation {
                     continue;
                 }
 
+                let coords = (x as i64 - self.center.0, y as i64 - self.center.1);
+
                 match state.get_event([
                     &self.field[y - 1][x],
                     &self.field[y][x - 1],
@@ -120,16 +265,181 @@ impl CellularAutomation {
                     &self.field[y][x + 1],
                 ]) {
                     Some(event) => {
-                        event_buffer
-                            .insert((x as i64 - self.center.0, y as i64 - self.center.1), event);
+                        event_buffer.insert(coords, event);
                     }
-                    _ => (),
-                };
+
+                    // Клетка без события остаётся в прежнем состоянии
+                    None if state.is_excited() => activity.excited.push(coords),
+                    None => {}
+                }
             }
         }
 
+        activity.changed.reserve(event_buffer.len());
+
         for ((x, y), event) in event_buffer {
-            self.set_cell((x, y), self.get_cell((x, y)).next(event));
+            let state = *self.get_cell((x, y));
+            let next = state.next(event);
+
+            if next != state {
+                activity.changed.push((x, y));
+            }
+
+            if next.is_excited() {
+                activity.excited.push((x, y));
+            }
+
+            self.set_cell((x, y), next);
+        }
+
+        activity
+    }
+}
+
+fn bounds_of(coords: impl Iterator<Item = (i64, i64)>) -> Option<Region> {
+    coords.fold(None, |bounds, (x, y)| match bounds {
+        None => Some(((x, y), (x, y))),
+        Some(((x0, y0), (x1, y1))) => Some(((x0.min(x), y0.min(y)), (x1.max(x), y1.max(y)))),
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::testing::non_empty;
+    use super::*;
+
+    // Несимметричная фигура: провод вправо, к которому сверху слева подходит сигнал
+    fn wire() -> CellularAutomation {
+        let mut ca = CellularAutomation::new();
+
+        for x in 0..6 {
+            ca.set_cell(
+                (x, 0),
+                State::NormalTransmitting {
+                    dir: Direction::Right,
+                    signal: x == 0,
+                },
+            );
+        }
+
+        ca.set_cell(
+            (-3, -2),
+            State::Confluence {
+                new: true,
+                old: false,
+            },
+        );
+        ca.set_cell((4, 3), State::Sensitive { value: 0b101 });
+
+        ca
+    }
+
+    #[test]
+    fn clone_keeps_orientation() {
+        let ca = wire();
+        let clone = ca.clone();
+
+        assert_eq!(clone.center, ca.center);
+        assert_eq!(clone.tact, ca.tact);
+        assert_eq!(non_empty(&clone), non_empty(&ca));
+
+        for ((x, y), state) in ca.cells() {
+            assert_eq!(clone.get_cell((x, y)), state, "клетка ({}, {})", x, y);
+        }
+    }
+
+    #[test]
+    fn fingerprint_depends_only_on_cells() {
+        let ca = wire();
+
+        // Расширение поля и такт не влияют на отпечаток
+        let mut expanded = ca.clone();
+        expanded.set_cell((100, -50), State::Unexcitable);
+        expanded.tact = 42;
+
+        assert_eq!(expanded.fingerprint(), ca.fingerprint());
+
+        let mut changed = ca.clone();
+        changed.set_cell((4, 3), State::Sensitive { value: 0b100 });
+
+        assert_ne!(changed.fingerprint(), ca.fingerprint());
+
+        // Те же клетки в другом месте - другой отпечаток
+        let shifted = CellularAutomation::from_cells(
+            &non_empty(&ca)
+                .into_iter()
+                .map(|((x, y), state)| ((x + 1, y), state))
+                .collect::<Vec<_>>(),
+            0,
+        );
+
+        assert_ne!(shifted.fingerprint(), ca.fingerprint());
+    }
+
+    #[test]
+    fn snapshot_restores_after_tacts() {
+        let mut ca = wire();
+
+        for _ in 0..3 {
+            ca.tact();
+        }
+
+        let snapshot = Snapshot::new(&ca);
+        let (cells, fingerprint) = (non_empty(&ca), ca.fingerprint());
+
+        for _ in 0..10 {
+            ca.tact();
+        }
+
+        assert_ne!(ca.fingerprint(), fingerprint);
+
+        let mut restored = snapshot.restore().unwrap();
+
+        assert_eq!(restored.tact, 3);
+        assert_eq!(restored.fingerprint(), fingerprint);
+        assert_eq!(non_empty(&restored), cells);
+
+        // Восстановленное поле считается дальше так же, как исходное
+        for _ in 0..10 {
+            restored.tact();
+        }
+
+        assert_eq!(restored.fingerprint(), ca.fingerprint());
+    }
+
+    #[test]
+    fn snapshot_detects_mismatch() {
+        let ca = wire();
+        let mut snapshot = Snapshot::new(&ca);
+
+        snapshot.automaton.set_cell((0, 0), State::Unexcitable);
+
+        assert!(snapshot.restore().is_err());
+    }
+
+    #[test]
+    fn activity_matches_field() {
+        let mut ca = wire();
+
+        for _ in 0..10 {
+            let before = ca.clone();
+            let activity = ca.tact_with_activity();
+
+            let mut excited: Vec<_> = ca
+                .cells()
+                .filter(|(_, state)| state.is_excited())
+                .map(|(coords, _)| coords)
+                .collect();
+            let mut reported = activity.excited.clone();
+
+            excited.sort();
+            reported.sort();
+
+            assert_eq!(reported, excited, "такт {}", ca.tact);
+
+            for coords in activity.changed {
+                assert_ne!(before.get_cell(coords), ca.get_cell(coords));
+            }
         }
     }
 }
@@ -37,6 +37,166 @@ pub enum State {
 }
 
 impl State {
+    // Номер состояния от 0 до 28: чувствительные по возрастанию кода, остальные - как в Golly.
+    // Для конфлюентных состояний сначала учитывается выдаваемое (old), а потом полученное (new) возбуждение.
+    pub fn index(&self) -> u8 {
+        use State::*;
+
+        let dir_index = |dir: Direction| match dir {
+            Direction::Right => 0,
+            Direction::Up => 1,
+            Direction::Left => 2,
+            Direction::Down => 3,
+        };
+
+        match *self {
+            Unexcitable => 0,
+
+            // Код чувствительного состояния хранится вместе с ведущей единицей, поэтому он от 1 до 8
+            Sensitive { value } => value,
+
+            Confluence { new, old } => 9 + old as u8 + 2 * new as u8,
+
+            NormalTransmitting { dir, signal } => 13 + dir_index(dir) + 4 * signal as u8,
+
+            SpecialTransmitting { dir, signal } => 21 + dir_index(dir) + 4 * signal as u8,
+        }
+    }
+
+    pub fn from_index(index: u8) -> Option<State> {
+        use State::*;
+
+        let dirs = [
+            Direction::Right,
+            Direction::Up,
+            Direction::Left,
+            Direction::Down,
+        ];
+
+        let state = match index {
+            0 => Unexcitable,
+
+            1..=8 => Sensitive { value: index },
+
+            9..=12 => Confluence {
+                new: (index - 9) & 0b10 != 0,
+                old: (index - 9) & 0b01 != 0,
+            },
+
+            13..=20 => NormalTransmitting {
+                dir: dirs[(index - 13) as usize % 4],
+                signal: index >= 17,
+            },
+
+            21..=28 => SpecialTransmitting {
+                dir: dirs[(index - 21) as usize % 4],
+                signal: index >= 25,
+            },
+
+            _ => return None,
+        };
+
+        Some(state)
+    }
+
+    // Передаёт ли клетка сигнал на этом такте
+    pub fn is_excited(&self) -> bool {
+        match *self {
+            State::NormalTransmitting { signal, .. }
+            | State::SpecialTransmitting { signal, .. } => signal,
+            State::Confluence { new, old } => new || old,
+            _ => false,
+        }
+    }
+
+    // Обозначение состояния как в README: U, S01, C10, T010, T131 и т.п.
+    // Для передающих состояний после T идёт тип (0 - обычное, 1 - специальное),
+    // направление (0 - вправо, 1 - вверх, 2 - влево, 3 - вниз) и возбуждение.
+    // Для конфлюентных - возбуждение, пришедшее на текущем такте (new), и на предыдущем (old).
+    pub fn notation(&self) -> String {
+        use State::*;
+
+        let dir_code = |dir: Direction| match dir {
+            Direction::Right => 0,
+            Direction::Up => 1,
+            Direction::Left => 2,
+            Direction::Down => 3,
+        };
+
+        match *self {
+            Unexcitable => "U".to_string(),
+
+            // Код записывается без ведущей единицы
+            Sensitive { value } => {
+                let bits = format!("{:b}", value);
+
+                format!("S{}", &bits[1..])
+            }
+
+            Confluence { new, old } => format!("C{}{}", new as u8, old as u8),
+
+            NormalTransmitting { dir, signal } => format!("T0{}{}", dir_code(dir), signal as u8),
+
+            SpecialTransmitting { dir, signal } => format!("T1{}{}", dir_code(dir), signal as u8),
+        }
+    }
+
+    pub fn from_notation(notation: &str) -> Option<State> {
+        use State::*;
+
+        let bit = |c: u8| match c {
+            b'0' => Some(false),
+            b'1' => Some(true),
+            _ => None,
+        };
+
+        let dir = |c: u8| match c {
+            b'0' => Some(Direction::Right),
+            b'1' => Some(Direction::Up),
+            b'2' => Some(Direction::Left),
+            b'3' => Some(Direction::Down),
+            _ => None,
+        };
+
+        let state = match notation.as_bytes() {
+            b"U" => Unexcitable,
+
+            [b'S', code @ ..] if code.len() <= 3 => {
+                let mut value = 1u8;
+
+                for &c in code {
+                    value = (value << 1) + bit(c)? as u8;
+                }
+
+                // Коды длиннее S000 сразу превращаются в другие состояния
+                if value > 0b1000 {
+                    return None;
+                }
+
+                Sensitive { value }
+            }
+
+            &[b'C', new, old] => Confluence {
+                new: bit(new)?,
+                old: bit(old)?,
+            },
+
+            &[b'T', b'0', d, signal] => NormalTransmitting {
+                dir: dir(d)?,
+                signal: bit(signal)?,
+            },
+
+            &[b'T', b'1', d, signal] => SpecialTransmitting {
+                dir: dir(d)?,
+                signal: bit(signal)?,
+            },
+
+            _ => return None,
+        };
+
+        Some(state)
+    }
+
     pub fn next(self, event: Event) -> State {
         use Direction::*;
         use Event::*;
@@ -0,0 +1,42 @@
+use super::{CellularAutomation, State};
+
+// Общие проверки полей для тестов этого модуля и форматов файлов
+
+// Непустые клетки поля по строкам сверху вниз
+pub fn non_empty(ca: &CellularAutomation) -> Vec<((i64, i64), State)> {
+    let mut cells: Vec<_> = ca
+        .cells()
+        .filter(|(_, state)| **state != State::Unexcitable)
+        .map(|(coords, state)| (coords, *state))
+        .collect();
+
+    cells.sort_by_key(|((x, y), _)| (*y, *x));
+    cells
+}
+
+// Состояние по обозначению вроде S01 или T131
+pub fn state(notation: &str) -> State {
+    State::from_notation(notation).unwrap()
+}
+
+// Все 29 состояний сеткой 6 x 5 с левым верхним углом в точке (x, y).
+// Невозбудимое состояние в углу оставляет прямоугольник с непустыми клетками тем же.
+pub fn all_states((x, y): (i64, i64)) -> CellularAutomation {
+    let mut ca = CellularAutomation::new();
+
+    for index in 0..29 {
+        ca.set_cell(
+            (x + index as i64 % 6, y + index as i64 / 6),
+            State::from_index(index).unwrap(),
+        );
+    }
+
+    ca
+}
+
+// Поле, прочитанное после записи, совпадает с исходным вместе с тактом
+pub fn assert_same_field(read: &CellularAutomation, ca: &CellularAutomation) {
+    assert_eq!(non_empty(read), non_empty(ca));
+    assert_eq!(read.bounds(), ca.bounds());
+    assert_eq!(read.tact, ca.tact);
+}
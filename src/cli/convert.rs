@@ -0,0 +1,17 @@
+use super::Args;
+use crate::files;
+use std::path::Path;
+
+pub fn convert(args: &[String]) -> Result<i32, String> {
+    let args = Args::parse(args, &[])?;
+
+    let input = args.positional(0, "исходный файл")?;
+    let output = args.positional(1, "файл результата")?;
+
+    let document = files::load(Path::new(input)).map_err(|err| err.to_string())?;
+
+    files::save(Path::new(output), &document.automaton, &document.metadata)
+        .map_err(|err| err.to_string())?;
+
+    Ok(0)
+}
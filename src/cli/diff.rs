@@ -0,0 +1,54 @@
+use super::Args;
+use crate::cellular_automaton::{self, Difference};
+use crate::files;
+use std::path::Path;
+
+pub fn diff(args: &[String]) -> Result<i32, String> {
+    let args = Args::parse(args, &[])?;
+
+    let first = args.positional(0, "первый файл")?;
+    let second = args.positional(1, "второй файл")?;
+
+    let a = files::load(Path::new(first)).map_err(|err| err.to_string())?;
+    let b = files::load(Path::new(second)).map_err(|err| err.to_string())?;
+
+    let (a, b) = (a.automaton, b.automaton);
+
+    if a.tact != b.tact {
+        println!("Такты различаются: {} и {}", a.tact, b.tact);
+    }
+
+    let differences = cellular_automaton::diff(&a, &b);
+
+    let (mut added, mut removed, mut changed) = (0, 0, 0);
+
+    for ((x, y), difference) in &differences {
+        match difference {
+            Difference::Added(state) => {
+                added += 1;
+                println!("+ ({}, {}) {}", x, y, state.notation());
+            }
+            Difference::Removed(state) => {
+                removed += 1;
+                println!("- ({}, {}) {}", x, y, state.notation());
+            }
+            Difference::Changed { from, to } => {
+                changed += 1;
+                println!("~ ({}, {}) {} -> {}", x, y, from.notation(), to.notation());
+            }
+        }
+    }
+
+    if differences.is_empty() {
+        println!("Поля совпадают");
+
+        Ok(0)
+    } else {
+        println!(
+            "Добавлено: {}, удалено: {}, изменено: {}",
+            added, removed, changed
+        );
+
+        Ok(1)
+    }
+}
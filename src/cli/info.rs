@@ -0,0 +1,83 @@
+use super::Args;
+use crate::cellular_automaton::State;
+use crate::files;
+use std::path::Path;
+
+pub fn info(args: &[String]) -> Result<i32, String> {
+    let args = Args::parse(args, &[])?;
+
+    let input = args.positional(0, "файл поля")?;
+
+    let document = files::load(Path::new(input)).map_err(|err| err.to_string())?;
+    let (ca, metadata) = (&document.automaton, &document.metadata);
+
+    println!("Правило: {}", metadata.rule);
+    println!("Такт: {}", ca.tact);
+
+    match ca.bounds() {
+        Some(((x0, y0), (x1, y1))) => println!(
+            "Границы: ({}, {}) - ({}, {}), {}x{}",
+            x0,
+            y0,
+            x1,
+            y1,
+            x1 - x0 + 1,
+            y1 - y0 + 1
+        ),
+        None => println!("Границы: поле пустое"),
+    }
+
+    // Количество клеток каждого состояния, кроме пустого
+    let mut histogram = [0usize; 29];
+
+    for (_, state) in ca.cells() {
+        if *state != State::Unexcitable {
+            histogram[state.index() as usize] += 1;
+        }
+    }
+
+    println!("Популяция: {}", ca.population());
+
+    for (index, count) in histogram
+        .iter()
+        .enumerate()
+        .filter(|(_, count)| **count > 0)
+    {
+        if let Some(state) = State::from_index(index as u8) {
+            println!("    {:<5} {}", state.notation(), count);
+        }
+    }
+
+    let fields = [
+        ("Название", &metadata.title),
+        ("Автор", &metadata.author),
+        ("Описание", &metadata.description),
+        ("Заметки", &metadata.notes),
+    ];
+
+    for (name, value) in fields {
+        if !value.is_empty() {
+            println!("{}: {}", name, value);
+        }
+    }
+
+    if let Some(viewport) = &metadata.viewport {
+        println!(
+            "Камера: смещение ({}, {}), размер клетки {}",
+            viewport.offset.0, viewport.offset.1, viewport.cell_size.0
+        );
+    }
+
+    if let Some(speed) = metadata.speed {
+        println!("Скорость: {}", speed);
+    }
+
+    for bookmark in &metadata.bookmarks {
+        println!(
+            "Закладка \"{}\": ({}, {})",
+            bookmark.name, bookmark.coords.0, bookmark.coords.1
+        );
+    }
+
+    Ok(0)
+}
@@ -0,0 +1,151 @@
+mod convert;
+mod diff;
+mod info;
+mod run;
+mod script;
+mod serve;
+mod tui;
+
+use std::collections::HashMap;
+
+// Команды для запуска без окна: kavn <команда> [аргументы]
+
+const USAGE: &str = "Использование:
+    kavn                                 запуск с окном
+    kavn run <файл> [параметры]          расчёт поля без окна
+    kavn convert <файл> <результат>      перевод поля в другой формат (по расширению)
+    kavn info <файл>                     границы, такт, количество клеток по состояниям
+                                         и описание поля
+    kavn diff <файл> <файл>              различающиеся клетки двух полей
+                                         (код завершения 1, если поля различаются)
+    kavn script <скрипт.rhai> [параметры]  выполнение скрипта
+    kavn serve [файл] [параметры]        управление по JSON-RPC (см. src/rpc/mod.rs)
+    kavn tui [файл]                      просмотр и редактирование поля в терминале
+
+Параметры run:
+    -n, --tacts <N>          количество тактов (по умолчанию 1)
+    --until <stable|empty>   считать до остановки поля или до его опустошения,
+                             -n в этом случае ограничивает число тактов
+    -o, --output <файл>      куда сохранить результат (формат по расширению)
+
+Параметры script:
+    -i, --input <файл>       поле, с которым работает скрипт (по умолчанию пустое)
+    -o, --output <файл>      куда сохранить поле после выполнения скрипта
+
+Параметры serve:
+    -s, --socket <путь>      принимать запросы через Unix-сокет, а не stdin/stdout
+    -w, --window             открыть окно и управлять полем в нём";
+
+// Возвращает код завершения программы. Команды тоже возвращают код завершения,
+// а об ошибках сообщают через Err.
+pub fn main(args: &[String]) -> i32 {
+    let result = match args[0].as_str() {
+        "run" => run::run(&args[1..]),
+        "convert" => convert::convert(&args[1..]),
+        "info" => info::info(&args[1..]),
+        "diff" => diff::diff(&args[1..]),
+        "script" => script::script(&args[1..]),
+        "serve" => serve::serve(&args[1..]),
+        "tui" => tui::tui(&args[1..]),
+        "help" | "-h" | "--help" => {
+            println!("{}", USAGE);
+
+            Ok(0)
+        }
+        command => Err(format!("неизвестная команда \"{}\"\n\n{}", command, USAGE)),
+    };
+
+    match result {
+        Ok(code) => code,
+        Err(err) => {
+            eprintln!("Ошибка: {}", err);
+
+            1
+        }
+    }
+}
+
+// Разобранные аргументы команды: позиционные и именованные параметры, а также флаги
+struct Args {
+    positional: Vec<String>,
+    options: HashMap<String, String>,
+    flags: Vec<String>,
+}
+
+impl Args {
+    // options - пары (короткое имя, длинное имя) параметров, принимающих значение
+    fn parse(args: &[String], options: &[(&str, &str)]) -> Result<Self, String> {
+        Self::parse_with_flags(args, options, &[])
+    }
+
+    // flags - пары имён параметров без значения
+    fn parse_with_flags(
+        args: &[String],
+        options: &[(&str, &str)],
+        flags: &[(&str, &str)],
+    ) -> Result<Self, String> {
+        let mut parsed = Args {
+            positional: vec![],
+            options: HashMap::new(),
+            flags: vec![],
+        };
+
+        let mut args = args.iter();
+
+        while let Some(arg) = args.next() {
+            if !arg.starts_with('-') {
+                parsed.positional.push(arg.clone());
+
+                continue;
+            }
+
+            if let Some((_, long)) = flags
+                .iter()
+                .find(|(short, long)| arg == short || arg == long)
+            {
+                parsed.flags.push(long.to_string());
+
+                continue;
+            }
+
+            let (_, long) = options
+                .iter()
+                .find(|(short, long)| arg == short || arg == long)
+                .ok_or_else(|| format!("неизвестный параметр \"{}\"", arg))?;
+
+            let value = args
+                .next()
+                .ok_or_else(|| format!("не указано значение параметра \"{}\"", arg))?;
+
+            parsed.options.insert(long.to_string(), value.clone());
+        }
+
+        Ok(parsed)
+    }
+
+    fn flag(&self, name: &str) -> bool {
+        self.flags.iter().any(|flag| flag == name)
+    }
+
+    fn get(&self, name: &str) -> Option<&str> {
+        self.options.get(name).map(String::as_str)
+    }
+
+    fn number(&self, name: &str) -> Result<Option<u64>, String> {
+        self.get(name)
+            .map(|value| {
+                value
+                    .parse()
+                    .map_err(|_| format!("\"{}\" не является числом ({})", value, name))
+            })
+            .transpose()
+    }
+
+    // Позиционный аргумент с номером i
+    fn positional(&self, i: usize, name: &str) -> Result<&str, String> {
+        self.positional
+            .get(i)
+            .map(String::as_str)
+            .ok_or_else(|| format!("не указан {}\n\n{}", name, USAGE))
+    }
+}
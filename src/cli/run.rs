@@ -0,0 +1,79 @@
+use super::Args;
+use crate::files;
+use std::path::Path;
+use std::time::Instant;
+
+// Условие, при котором расчёт останавливается раньше заданного числа тактов
+enum Until {
+    // Поле перестало меняться
+    Stable,
+    // На поле не осталось непустых клеток
+    Empty,
+}
+
+pub fn run(args: &[String]) -> Result<i32, String> {
+    let args = Args::parse(
+        args,
+        &[("-n", "--tacts"), ("", "--until"), ("-o", "--output")],
+    )?;
+
+    let input = args.positional(0, "файл поля")?;
+
+    let until = match args.get("--until") {
+        None => None,
+        Some("stable") => Some(Until::Stable),
+        Some("empty") => Some(Until::Empty),
+        Some(other) => return Err(format!("неизвестное условие остановки \"{}\"", other)),
+    };
+
+    // С условием остановки число тактов по умолчанию не ограничено
+    let tacts = args.number("--tacts")?.unwrap_or(match until {
+        None => 1,
+        Some(_) => u64::MAX,
+    });
+
+    let document = files::load(Path::new(input)).map_err(|err| err.to_string())?;
+    let mut ca = document.automaton;
+
+    let start_tact = ca.tact;
+    let now = Instant::now();
+
+    let mut fingerprint = ca.fingerprint();
+
+    for _ in 0..tacts {
+        if let Some(Until::Empty) = until {
+            if ca.population() == 0 {
+                break;
+            }
+        }
+
+        ca.tact();
+
+        if let Some(Until::Stable) = until {
+            let next = ca.fingerprint();
+
+            if next == fingerprint {
+                break;
+            }
+
+            fingerprint = next;
+        }
+    }
+
+    let elapsed = now.elapsed();
+    let done = ca.tact - start_tact;
+
+    println!("Такт: {} (рассчитано {})", ca.tact, done);
+    println!(
+        "Время: {:.3} с ({:.1} тактов/с)",
+        elapsed.as_secs_f64(),
+        done as f64 / elapsed.as_secs_f64().max(f64::EPSILON)
+    );
+    println!("Популяция: {}", ca.population());
+
+    if let Some(output) = args.get("--output") {
+        files::save(Path::new(output), &ca, &document.metadata).map_err(|err| err.to_string())?;
+    }
+
+    Ok(0)
+}
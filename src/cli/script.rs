@@ -0,0 +1,38 @@
+use super::Args;
+use crate::cellular_automaton::CellularAutomation;
+use crate::files::{self, Metadata};
+use crate::script::{self, Script};
+use std::fs;
+use std::path::Path;
+
+pub fn script(args: &[String]) -> Result<i32, String> {
+    let args = Args::parse(args, &[("-i", "--input"), ("-o", "--output")])?;
+
+    let path = args.positional(0, "файл скрипта")?;
+    let source = fs::read_to_string(path).map_err(|err| format!("{}: {}", path, err))?;
+
+    let mut script = match args.get("--input") {
+        None => Script::new(CellularAutomation::new(), Metadata::default()),
+        Some(input) => {
+            let document = files::load(Path::new(input)).map_err(|err| err.to_string())?;
+
+            Script::new(document.automaton, document.metadata)
+        }
+    };
+
+    script.echo = true;
+
+    // В терминале зациклившийся скрипт можно прервать через Ctrl+C
+    script.max_operations = 0;
+
+    let (script, result) = script::run(&source, script);
+
+    result?;
+
+    if let Some(output) = args.get("--output") {
+        files::save(Path::new(output), &script.automaton, &script.metadata)
+            .map_err(|err| err.to_string())?;
+    }
+
+    Ok(0)
+}
@@ -0,0 +1,76 @@
+use super::Args;
+use crate::cellular_automaton::CellularAutomation;
+use crate::files::{self, Document, Metadata};
+use crate::rpc::{self, Headless, Request, Server};
+use std::path::Path;
+use std::sync::mpsc::{Receiver, TryRecvError};
+
+pub fn serve(args: &[String]) -> Result<i32, String> {
+    let args = Args::parse_with_flags(args, &[("-s", "--socket")], &[("-w", "--window")])?;
+
+    let document = match args.positional.first() {
+        None => Document {
+            automaton: CellularAutomation::new(),
+            metadata: Metadata::default(),
+        },
+        Some(path) => files::load(Path::new(path)).map_err(|err| err.to_string())?,
+    };
+
+    let receiver = match args.get("--socket") {
+        None => rpc::stdio(),
+        Some(path) => listen(path)?,
+    };
+
+    if args.flag("--window") {
+        crate::open_window(Some(document), Some(receiver));
+    } else {
+        run_headless(document, receiver);
+    }
+
+    Ok(0)
+}
+
+#[cfg(unix)]
+fn listen(path: &str) -> Result<Receiver<Request>, String> {
+    rpc::unix_socket(Path::new(path)).map_err(|err| format!("{}: {}", path, err))
+}
+
+#[cfg(not(unix))]
+fn listen(_: &str) -> Result<Receiver<Request>, String> {
+    Err("Unix-сокеты на этой системе недоступны".to_string())
+}
+
+// Без окна поле считается между запросами, пока расчёт не поставлен на паузу.
+// Сервер завершается, когда закрывается источник запросов (например, stdin).
+fn run_headless(document: Document, receiver: Receiver<Request>) {
+    let mut simulator = Headless {
+        automaton: document.automaton,
+        metadata: document.metadata,
+        paused: true,
+    };
+
+    let mut server = Server::default();
+
+    loop {
+        let request = if simulator.paused {
+            match receiver.recv() {
+                Ok(request) => request,
+                Err(_) => break,
+            }
+        } else {
+            match receiver.try_recv() {
+                Ok(request) => request,
+                Err(TryRecvError::Empty) => {
+                    simulator.automaton.tact();
+
+                    continue;
+                }
+                Err(TryRecvError::Disconnected) => break,
+            }
+        };
+
+        if let Some(response) = server.handle(&mut simulator, &request.line) {
+            request.respond(&response);
+        }
+    }
+}
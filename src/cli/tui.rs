@@ -0,0 +1,27 @@
+use super::Args;
+use crate::cellular_automaton::CellularAutomation;
+use crate::files::{self, Document, Metadata};
+use crate::tui;
+use std::path::{Path, PathBuf};
+
+pub fn tui(args: &[String]) -> Result<i32, String> {
+    let args = Args::parse(args, &[])?;
+
+    // Поле сохраняется в тот же файл, из которого загружено. Если файла ещё нет,
+    // работа начинается с пустого поля.
+    let path = args.positional.first().map(PathBuf::from);
+
+    let document = match &path {
+        Some(path) if path.exists() => {
+            files::load(Path::new(path)).map_err(|err| err.to_string())?
+        }
+        _ => Document {
+            automaton: CellularAutomation::new(),
+            metadata: Metadata::default(),
+        },
+    };
+
+    tui::run(document, path).map_err(|err| err.to_string())?;
+
+    Ok(0)
+}
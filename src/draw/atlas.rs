@@ -0,0 +1,100 @@
+use super::{draw_state, RasterCanvas, Theme};
+use crate::cellular_automaton::State;
+use macroquad::prelude::{
+    draw_texture_ex, vec2, Color, DrawTextureParams, FilterMode, Rect, Texture2D, WHITE,
+};
+
+// Изображения всех 29 состояний, заранее нарисованные в одну текстуру.
+// Клетки рисуются частями этой текстуры, а macroquad объединяет такие вызовы
+// с одной текстурой в общий пакет, поэтому кадр с тысячами клеток рисуется
+// за несколько вызовов видеокарты вместо тысяч линий и кругов.
+
+// Изображения в текстуре лежат сеткой 6 x 5
+const COLUMNS: u8 = 6;
+const ROWS: u8 = 5;
+
+// Прозрачная рамка вокруг каждого изображения: при сглаживании текстуры
+// (FilterMode::Linear) на край клетки не попадают пиксели соседнего состояния
+const PADDING: f32 = 1.0;
+
+// Больше этого размера изображения не рисуются, а растягиваются при выводе,
+// чтобы текстура не выходила за ограничения видеокарты
+const MAX_GLYPH: f32 = 256.0;
+
+pub struct Atlas {
+    texture: Texture2D,
+    glyph: f32,
+
+    // Размер клеток и тема, для которых нарисована текстура
+    pub cell_size: (f32, f32),
+    pub theme: Theme,
+}
+
+impl Atlas {
+    pub fn new(cell_size: (f32, f32), theme: Theme) -> Self {
+        let glyph = cell_size.0.max(cell_size.1).ceil().clamp(1.0, MAX_GLYPH);
+        let tile = (glyph + 2.0 * PADDING) as u32;
+        let (width, height) = (tile * COLUMNS as u32, tile * ROWS as u32);
+
+        let mut canvas = RasterCanvas::new(width, height, Color::new(0.0, 0.0, 0.0, 0.0));
+
+        for index in 0..29 {
+            if let Some(state) = State::from_index(index) {
+                let (x, y) = Self::origin(index, glyph);
+
+                // Круг чувствительного состояния немного больше клетки и обрезается по её краю
+                canvas.set_clip(Some((x as u32, y as u32, glyph as u32, glyph as u32)));
+
+                draw_state(&mut canvas, (x, y), (glyph, glyph), &state, &theme);
+            }
+        }
+
+        let texture = Texture2D::from_rgba8(width as u16, height as u16, &canvas.pixels);
+        texture.set_filter(FilterMode::Linear);
+
+        Self {
+            texture,
+            glyph,
+            cell_size,
+            theme,
+        }
+    }
+
+    // Левый верхний угол изображения состояния в текстуре (без рамки)
+    fn origin(index: u8, glyph: f32) -> (f32, f32) {
+        let tile = glyph + 2.0 * PADDING;
+
+        (
+            (index % COLUMNS) as f32 * tile + PADDING,
+            (index / COLUMNS) as f32 * tile + PADDING,
+        )
+    }
+
+    pub fn draw(&self, state: &State, (x, y): (f32, f32)) {
+        // У невозбудимой клетки нет изображения
+        if *state == State::Unexcitable {
+            return;
+        }
+
+        let (sx, sy) = Self::origin(state.index(), self.glyph);
+
+        draw_texture_ex(
+            self.texture,
+            x,
+            y,
+            WHITE,
+            DrawTextureParams {
+                dest_size: Some(vec2(self.cell_size.0, self.cell_size.1)),
+                source: Some(Rect::new(sx, sy, self.glyph, self.glyph)),
+                ..Default::default()
+            },
+        );
+    }
+}
+
+// Текстуры macroquad не освобождаются сами, а атлас пересоздаётся при каждом изменении масштаба
+impl Drop for Atlas {
+    fn drop(&mut self) {
+        self.texture.delete();
+    }
+}
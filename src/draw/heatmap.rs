@@ -0,0 +1,101 @@
+use super::CellularAutomatonPainter;
+use crate::cellular_automaton::Activity;
+use macroquad::prelude::Color;
+use macroquad::shapes::draw_rectangle;
+use std::collections::{HashMap, HashSet};
+
+// Активность клеток: сколько раз за учтённые такты клетка меняла состояние или передавала сигнал
+#[derive(Default)]
+pub struct Heatmap {
+    heat: HashMap<(i64, i64), f32>,
+
+    // Через сколько тактов старая активность учитывается вдвое слабее (0 - без затухания)
+    pub half_life: u32,
+
+    // Сколько тактов учтено с последнего сброса
+    pub tacts: u64,
+}
+
+impl Heatmap {
+    // Учёт такта: активны клетки, изменившие состояние или возбуждённые после него
+    pub fn record(&mut self, activity: &Activity) {
+        if self.half_life > 0 {
+            let k = 0.5_f32.powf(1.0 / self.half_life as f32);
+
+            // Почти остывшие клетки удаляются, чтобы карта не росла бесконечно
+            self.heat.retain(|_, heat| {
+                *heat *= k;
+                *heat >= 0.01
+            });
+        }
+
+        let active: HashSet<(i64, i64)> = activity
+            .changed
+            .iter()
+            .chain(&activity.excited)
+            .copied()
+            .collect();
+
+        for coords in active {
+            *self.heat.entry(coords).or_default() += 1.0;
+        }
+
+        self.tacts += 1;
+    }
+
+    pub fn reset(&mut self) {
+        self.heat.clear();
+        self.tacts = 0;
+    }
+
+    // Наибольшая активность, относительно которой раскрашивается карта
+    pub fn max(&self) -> f32 {
+        self.heat.values().copied().fold(0.0, f32::max)
+    }
+}
+
+// Цвет активности t от 0 до 1: от синего через жёлтый к красному
+pub fn heat_color(t: f32) -> Color {
+    let t = t.clamp(0.0, 1.0);
+
+    if t < 0.5 {
+        let k = t * 2.0;
+
+        Color::new(k, k, 1.0 - k, 1.0)
+    } else {
+        let k = (t - 0.5) * 2.0;
+
+        Color::new(1.0, 1.0 - k, 0.0, 1.0)
+    }
+}
+
+impl CellularAutomatonPainter {
+    // Полупрозрачная тепловая карта поверх поля
+    pub fn draw_heatmap(&self, heatmap: &Heatmap) {
+        let max = heatmap.max();
+
+        if max <= 0.0 {
+            return;
+        }
+
+        let ((x0, y0), (x1, y1)) = self.visible_region();
+
+        for (&(x, y), &heat) in &heatmap.heat {
+            if x < x0 || x > x1 || y < y0 || y > y1 {
+                continue;
+            }
+
+            // При мелком масштабе клетка закрашивается хотя бы одним пикселем
+            draw_rectangle(
+                self.cell_size.0 * x as f32 + self.offset.0,
+                self.cell_size.1 * y as f32 + self.offset.1,
+                self.cell_size.0.max(1.0),
+                self.cell_size.1.max(1.0),
+                Color {
+                    a: 0.55,
+                    ..heat_color(heat / max)
+                },
+            );
+        }
+    }
+}
@@ -0,0 +1,138 @@
+use super::{color, CellularAutomatonPainter};
+use crate::cellular_automaton::Region;
+use macroquad::prelude::{draw_texture_ex, vec2, Color, DrawTextureParams, Rect, WHITE};
+use macroquad::shapes::{draw_rectangle, draw_rectangle_lines};
+use macroquad::window::screen_height;
+
+// Мини-карта: все непустые клетки в уменьшенном виде в левом нижнем углу окна
+// и рамка вокруг видимой на экране части поля
+
+// Наибольшая ширина и высота мини-карты в пикселях
+const SIZE: f32 = 200.0;
+const MARGIN: f32 = 10.0;
+
+// Прямоугольник мини-карты на экране, показанная на ней область поля и размер клетки
+// на мини-карте. Считается один раз за кадр и нужен и для рисования, и для мыши.
+#[derive(Clone, Copy)]
+pub struct Minimap {
+    rect: Rect,
+    region: Region,
+    scale: f32,
+}
+
+impl Minimap {
+    pub fn contains(&self, (x, y): (f32, f32)) -> bool {
+        self.rect.contains(vec2(x, y))
+    }
+
+    // Клетка поля под точкой мини-карты. Точка за краем мини-карты
+    // считается лежащей на краю, чтобы камеру можно было дотянуть до границы поля.
+    pub fn cell_at(&self, (x, y): (f32, f32)) -> (i64, i64) {
+        let (rect, ((x0, y0), (x1, y1))) = (self.rect, self.region);
+
+        let (x, y) = (
+            x.clamp(rect.x, rect.right()),
+            y.clamp(rect.y, rect.bottom()),
+        );
+
+        (
+            (x0 + ((x - rect.x) / self.scale).floor() as i64).min(x1),
+            (y0 + ((y - rect.y) / self.scale).floor() as i64).min(y1),
+        )
+    }
+}
+
+impl CellularAutomatonPainter {
+    // Мини-карта, отступающая от левого края окна на left (на ширину линейки, если она
+    // показана). Для пустого поля мини-карты нет.
+    pub fn minimap(&self, left: f32) -> Option<Minimap> {
+        let ((x0, y0), (x1, y1)) = self.bounds()?;
+
+        let (width, height) = ((x1 - x0 + 1) as f32, (y1 - y0 + 1) as f32);
+        let scale = (SIZE / width).min(SIZE / height);
+        let (width, height) = (width * scale, height * scale);
+
+        Some(Minimap {
+            rect: Rect::new(
+                left + MARGIN,
+                screen_height() - MARGIN - height,
+                width,
+                height,
+            ),
+            region: ((x0, y0), (x1, y1)),
+            scale,
+        })
+    }
+
+    pub fn draw_minimap(&self, minimap: &Minimap) {
+        let Minimap {
+            rect,
+            region,
+            scale,
+        } = *minimap;
+
+        draw_rectangle(
+            rect.x - 2.0,
+            rect.y - 2.0,
+            rect.w + 4.0,
+            rect.h + 4.0,
+            color(self.theme.background),
+        );
+        draw_rectangle_lines(
+            rect.x - 2.0,
+            rect.y - 2.0,
+            rect.w + 4.0,
+            rect.h + 4.0,
+            1.0,
+            color(self.theme.grid),
+        );
+
+        let block = (1.0 / scale).ceil() as i64;
+
+        let (texture, (width, height)) = self.minimap.borrow_mut().get(
+            self,
+            &self.cellular_automaton.read().unwrap(),
+            region,
+            region.0,
+            block,
+        );
+
+        draw_texture_ex(
+            texture,
+            rect.x,
+            rect.y,
+            WHITE,
+            DrawTextureParams {
+                dest_size: Some(vec2(
+                    (width as i64 * block) as f32 * scale,
+                    (height as i64 * block) as f32 * scale,
+                )),
+                ..Default::default()
+            },
+        );
+
+        // Рамка видимой части, обрезанная по краям мини-карты
+        let ((x0, y0), _) = region;
+        let ((vx0, vy0), (vx1, vy1)) = self.visible_region();
+
+        let (left, top) = (
+            (rect.x + (vx0 - x0) as f32 * scale).max(rect.x),
+            (rect.y + (vy0 - y0) as f32 * scale).max(rect.y),
+        );
+        let (right, bottom) = (
+            (rect.x + (vx1 - x0 + 1) as f32 * scale).min(rect.right()),
+            (rect.y + (vy1 - y0 + 1) as f32 * scale).min(rect.bottom()),
+        );
+
+        if left < right && top < bottom {
+            draw_rectangle_lines(
+                left,
+                top,
+                (right - left).max(2.0),
+                (bottom - top).max(2.0),
+                2.0,
+                Color::new(0.0, 0.4, 1.0, 1.0),
+            );
+        }
+    }
+}
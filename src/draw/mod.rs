@@ -1,15 +1,71 @@
-use macroquad::prelude::{draw_circle, draw_line, Color};
-use macroquad::shapes::draw_rectangle;
+mod atlas;
+mod heatmap;
+mod minimap;
+mod raster;
+mod rulers;
+mod theme;
+
+use macroquad::prelude::{
+    draw_line, draw_texture_ex, vec2, Color, DrawTextureParams, FilterMode, Image, Texture2D, WHITE,
+};
+use macroquad::shapes::{draw_rectangle, draw_rectangle_lines};
 use macroquad::window::{screen_height, screen_width};
+use std::cell::RefCell;
 use std::rc::Rc;
 use std::sync::RwLock;
+use std::time::{Duration, Instant};
+
+pub use atlas::*;
+pub use heatmap::*;
+pub use minimap::*;
+pub use raster::*;
+pub use rulers::*;
+pub use theme::*;
 
 use crate::cellular_automaton::*;
 
+// Наименьший размер клетки (1/16 пикселя), до которого можно отдалить поле
+pub const MIN_CELL_SIZE: f32 = 1.0 / 16.0;
+
+// При клетках меньше этого размера вместо изображений состояний рисуются цвета
+const GLYPH_MIN_SIZE: f32 = 6.0;
+
+// Пока поле меняется каждый такт, изображения мелкого масштаба перестраиваются не чаще этого
+const FLAT_REFRESH: Duration = Duration::from_millis(250);
+
+// Сетка рисуется, только если клетки достаточно крупные, иначе она закрывает поле
+const GRID_MIN_SIZE: f32 = 8.0;
+
+// Примитивы, из которых состоят изображения клеток.
+// Рисовать можно как на экран, так и в изображение без открытия окна (для экспорта)
+pub trait Canvas {
+    fn line(&mut self, x1: f32, y1: f32, x2: f32, y2: f32, thickness: f32, color: Color);
+    fn circle(&mut self, x: f32, y: f32, r: f32, color: Color);
+    fn rectangle(&mut self, x: f32, y: f32, w: f32, h: f32, color: Color);
+}
+
 pub struct CellularAutomatonPainter {
     pub cellular_automaton: Rc<RwLock<CellularAutomation>>,
     pub offset: (f32, f32),
     pub cell_size: (f32, f32),
+    pub theme: Theme,
+
+    // Изображения состояний для текущего размера клеток, перерисовываются при его изменении
+    atlas: RefCell<Option<Atlas>>,
+
+    // Номер изменения поля, увеличивается в field_changed. По нему определяется,
+    // устарели ли построенные по клеткам изображения.
+    revision: u64,
+
+    // Текстура поля для мелкого масштаба: один пиксель - одна клетка или группа клеток
+    overview: RefCell<FlatTexture>,
+
+    // Прямоугольник с непустыми клетками для номера изменения поля, чтобы не обходить
+    // поле каждый кадр
+    bounds: RefCell<Option<(u64, Option<Region>)>>,
+
+    // Текстура мини-карты
+    minimap: RefCell<FlatTexture>,
 }
 
 impl CellularAutomatonPainter {
@@ -18,6 +74,36 @@ impl CellularAutomatonPainter {
             cellular_automaton,
             cell_size,
             offset: (0.0, 0.0),
+            theme: Theme::default(),
+            atlas: RefCell::new(None),
+            revision: 0,
+            overview: RefCell::new(FlatTexture::default()),
+            bounds: RefCell::new(None),
+            minimap: RefCell::new(FlatTexture::default()),
+        }
+    }
+
+    // Вызывается после любого изменения клеток поля
+    pub fn field_changed(&mut self) {
+        self.revision += 1;
+    }
+
+    fn bounds(&self) -> Option<Region> {
+        self.field_bounds(&self.cellular_automaton.read().unwrap())
+    }
+
+    // То же, что bounds, для уже заблокированного для чтения поля
+    fn field_bounds(&self, ca: &CellularAutomation) -> Option<Region> {
+        let mut cached = self.bounds.borrow_mut();
+
+        match *cached {
+            Some((revision, bounds)) if revision == self.revision => bounds,
+            _ => {
+                let bounds = ca.bounds();
+
+                *cached = Some((self.revision, bounds));
+                bounds
+            }
         }
     }
 
@@ -25,7 +111,8 @@ impl CellularAutomatonPainter {
         let (ow, oh) = self.cell_size;
         let (nw, nh) = new_size;
 
-        if nw < 8.0 || nh < 8.0 {
+        // Сравнение записано так, чтобы NaN тоже отбрасывался
+        if !(nw >= MIN_CELL_SIZE && nh >= MIN_CELL_SIZE) || nw.is_infinite() || nh.is_infinite() {
             return;
         }
 
@@ -37,382 +124,686 @@ impl CellularAutomatonPainter {
         self.offset.1 *= kh;
     }
 
-    fn draw_state(&self, coords: (f32, f32), state: &State) {
-        match *state {
-            State::Unexcitable => {}
+    // Перемещение камеры так, чтобы клетка оказалась в центре экрана
+    pub fn center_on(&mut self, (x, y): (i64, i64)) {
+        self.offset = (
+            screen_width() / 2.0 - (x as f32 + 0.5) * self.cell_size.0,
+            screen_height() / 2.0 - (y as f32 + 0.5) * self.cell_size.1,
+        );
+    }
 
-            State::Sensitive { mut value } => {
-                let mut signals: Vec<bool> = vec![];
+    // Клетка, находящаяся в центре экрана
+    pub fn center_cell(&self) -> (i64, i64) {
+        (
+            ((screen_width() / 2.0 - self.offset.0) / self.cell_size.0).floor() as i64,
+            ((screen_height() / 2.0 - self.offset.1) / self.cell_size.1).floor() as i64,
+        )
+    }
 
-                let mut count = 0;
-                while value & 0b10000000 != 0b10000000 {
-                    value = value << 1;
-                    count += 1;
-                }
+    // Клетка, в которую попадает точка экрана
+    pub fn cell_at(&self, (x, y): (f32, f32)) -> (i64, i64) {
+        // Применение смещения
+        let (x, y) = (x - self.offset.0, y - self.offset.1);
 
-                for _ in 0..(8 - count) {
-                    value = value << 1;
-                    signals.push(value & 0b10000000 == 0b10000000);
-                }
+        // Приведение к координатам ячеек
+        (
+            (x / self.cell_size.0).ceil() as i64 - 1,
+            (y / self.cell_size.1).ceil() as i64 - 1,
+        )
+    }
 
-                let (cx, cy) = (
-                    coords.0 + self.cell_size.0 / 2.0,
-                    coords.1 + self.cell_size.1 / 2.0,
-                );
-                for (v, &k) in signals.into_iter().zip([1.0, 0.75, 0.5, 0.25].iter()) {
-                    draw_circle(
-                        cx,
-                        cy,
-                        self.cell_size.0 / 1.9 * k,
-                        if v {
-                            Color::new(0.0, 1.0, 0.0, 1.0)
-                        } else {
-                            Color::new(1.0, 1.0, 1.0, 1.0)
-                        },
-                    );
-                    draw_circle(
-                        cx,
-                        cy,
-                        self.cell_size.0 / 2.0 * k,
-                        if v {
-                            Color::new(0.0, 1.0, 0.0, 1.0)
-                        } else {
-                            Color::new(0.0, 0.0, 0.0, 1.0)
-                        },
-                    );
-                }
-            }
+    pub fn draw_selection(&self, ((x0, y0), (x1, y1)): Region) {
+        draw_rectangle(
+            self.cell_size.0 * x0 as f32 + self.offset.0,
+            self.cell_size.1 * y0 as f32 + self.offset.1,
+            self.cell_size.0 * (x1 - x0 + 1) as f32,
+            self.cell_size.1 * (y1 - y0 + 1) as f32,
+            Color::new(0.0, 0.4, 1.0, 0.25),
+        );
+    }
 
-            State::NormalTransmitting {
-                dir: Direction::Up,
-                signal,
-            } => {
-                let c = if signal {
-                    Color::new(0.0, 1.0, 0.0, 1.0)
-                } else {
-                    Color::new(0.0, 0.0, 0.0, 1.0)
-                };
+    // Подсветка отличий от другого поля: добавленные клетки зелёные,
+    // удалённые красные, изменённые оранжевые
+    pub fn draw_differences(&self, differences: &[((i64, i64), Difference)]) {
+        for ((x, y), difference) in differences {
+            let color = match difference {
+                Difference::Added(_) => Color::new(0.0, 0.8, 0.0, 1.0),
+                Difference::Removed(_) => Color::new(1.0, 0.0, 0.0, 1.0),
+                Difference::Changed { .. } => Color::new(1.0, 0.6, 0.0, 1.0),
+            };
+
+            let (px, py) = (
+                self.cell_size.0 * *x as f32 + self.offset.0,
+                self.cell_size.1 * *y as f32 + self.offset.1,
+            );
 
-                draw_line(
-                    coords.0 + self.cell_size.0 / 2.0,
-                    coords.1 + self.cell_size.1 / 8.0,
-                    coords.0 + self.cell_size.0 / 2.0 - self.cell_size.0 / 4.0,
-                    coords.1 + self.cell_size.1 * 7.0 / 8.0,
-                    self.cell_size.0 / 10.0,
-                    c,
-                );
+            draw_rectangle(
+                px,
+                py,
+                self.cell_size.0,
+                self.cell_size.1,
+                Color { a: 0.3, ..color },
+            );
+            draw_rectangle_lines(
+                px,
+                py,
+                self.cell_size.0,
+                self.cell_size.1,
+                self.cell_size.0 / 10.0,
+                color,
+            );
+        }
+    }
 
-                draw_line(
-                    coords.0 + self.cell_size.0 / 2.0,
-                    coords.1 + self.cell_size.1 / 8.0,
-                    coords.0 + self.cell_size.0 / 2.0 + self.cell_size.0 / 4.0,
-                    coords.1 + self.cell_size.1 * 7.0 / 8.0,
-                    self.cell_size.0 / 10.0,
-                    c,
-                );
-            }
+    // Клетки, хотя бы частично попадающие на экран
+    pub fn visible_region(&self) -> Region {
+        let (x0, y0) = self.cell_at((0.0, 0.0));
+        let (x1, y1) = self.cell_at((screen_width(), screen_height()));
 
-            State::NormalTransmitting {
-                dir: Direction::Left,
-                signal,
-            } => {
-                let c = if signal {
-                    Color::new(0.0, 1.0, 0.0, 1.0)
-                } else {
-                    Color::new(0.0, 0.0, 0.0, 1.0)
-                };
+        ((x0, y0), (x1, y1))
+    }
 
-                draw_line(
-                    coords.0 + self.cell_size.0 / 8.0,
-                    coords.1 + self.cell_size.1 / 2.0,
-                    coords.0 + self.cell_size.0 * 7.0 / 8.0,
-                    coords.1 + self.cell_size.1 / 2.0 - self.cell_size.1 / 4.0,
-                    self.cell_size.0 / 10.0,
-                    c,
-                );
+    pub fn draw(&self) {
+        let (cx, cy) = self.cell_size;
 
+        if cx.min(cy) >= GRID_MIN_SIZE {
+            // Линии сетки начинаются с ближайшей к краю экрана границы клетки
+            let mut x = self.offset.0.rem_euclid(cx);
+            while x < screen_width() {
                 draw_line(
-                    coords.0 + self.cell_size.0 / 8.0,
-                    coords.1 + self.cell_size.1 / 2.0,
-                    coords.0 + self.cell_size.0 * 7.0 / 8.0,
-                    coords.1 + self.cell_size.1 / 2.0 + self.cell_size.1 / 4.0,
-                    self.cell_size.0 / 10.0,
-                    c,
+                    x,
+                    0.0,
+                    x,
+                    screen_height(),
+                    cx / 20.0,
+                    color(self.theme.grid),
                 );
+                x += cx;
             }
 
-            State::NormalTransmitting {
-                dir: Direction::Down,
-                signal,
-            } => {
-                let c = if signal {
-                    Color::new(0.0, 1.0, 0.0, 1.0)
-                } else {
-                    Color::new(0.0, 0.0, 0.0, 1.0)
-                };
+            let mut y = self.offset.1.rem_euclid(cy);
+            while y < screen_height() {
+                draw_line(0.0, y, screen_width(), y, cy / 20.0, color(self.theme.grid));
+                y += cy;
+            }
+        }
 
-                draw_line(
-                    coords.0 + self.cell_size.0 / 2.0,
-                    coords.1 + self.cell_size.1 - self.cell_size.1 / 8.0,
-                    coords.0 + self.cell_size.0 / 2.0 - self.cell_size.0 / 4.0,
-                    coords.1 + self.cell_size.1 - self.cell_size.1 * 7.0 / 8.0,
-                    self.cell_size.0 / 10.0,
-                    c,
-                );
+        let ca = self.cellular_automaton.read().unwrap();
 
-                draw_line(
-                    coords.0 + self.cell_size.0 / 2.0,
-                    coords.1 + self.cell_size.1 - self.cell_size.1 / 8.0,
-                    coords.0 + self.cell_size.0 / 2.0 + self.cell_size.0 / 4.0,
-                    coords.1 + self.cell_size.1 - self.cell_size.1 * 7.0 / 8.0,
-                    self.cell_size.0 / 10.0,
-                    c,
-                );
-            }
+        // Рисуются только клетки на пересечении поля и экрана
+        let ((x0, y0), (x1, y1)) = self.visible_region();
+        let region = (
+            (x0.max(-ca.center.0), y0.max(-ca.center.1)),
+            (
+                x1.min(ca.field[0].len() as i64 - ca.center.0 - 1),
+                y1.min(ca.field.len() as i64 - ca.center.1 - 1),
+            ),
+        );
+
+        if cx.min(cy) >= GLYPH_MIN_SIZE {
+            self.draw_glyphs(&ca, region);
+        } else {
+            self.draw_overview(&ca, region);
+        }
+    }
 
-            State::NormalTransmitting {
-                dir: Direction::Right,
-                signal,
-            } => {
-                let c = if signal {
-                    Color::new(0.0, 1.0, 0.0, 1.0)
-                } else {
-                    Color::new(0.0, 0.0, 0.0, 1.0)
-                };
+    fn draw_glyphs(&self, ca: &CellularAutomation, ((x0, y0), (x1, y1)): Region) {
+        let mut atlas = self.atlas.borrow_mut();
+        if !matches!(&*atlas, Some(atlas) if atlas.cell_size == self.cell_size && atlas.theme == self.theme)
+        {
+            *atlas = Some(Atlas::new(self.cell_size, self.theme));
+        }
+        let atlas = atlas.as_ref().unwrap();
 
-                draw_line(
-                    coords.0 + self.cell_size.0 - self.cell_size.0 / 8.0,
-                    coords.1 + self.cell_size.1 / 2.0,
-                    coords.0 + self.cell_size.0 - self.cell_size.0 * 7.0 / 8.0,
-                    coords.1 + self.cell_size.1 / 2.0 - self.cell_size.1 / 4.0,
-                    self.cell_size.0 / 10.0,
-                    c,
-                );
+        for y in y0..=y1 {
+            for x in x0..=x1 {
+                let state = ca.get_cell((x, y));
+                let (x, y) = (x as f32, y as f32);
 
-                draw_line(
-                    coords.0 + self.cell_size.0 - self.cell_size.0 / 8.0,
-                    coords.1 + self.cell_size.1 / 2.0,
-                    coords.0 + self.cell_size.0 - self.cell_size.0 * 7.0 / 8.0,
-                    coords.1 + self.cell_size.1 / 2.0 + self.cell_size.1 / 4.0,
-                    self.cell_size.0 / 10.0,
-                    c,
+                atlas.draw(
+                    state,
+                    (
+                        self.cell_size.0 * x + self.offset.0,
+                        self.cell_size.1 * y + self.offset.1,
+                    ),
                 );
             }
+        }
+    }
 
-            State::SpecialTransmitting {
-                dir: Direction::Up,
-                signal,
-            } => {
-                let c = if signal {
-                    Color::new(0.0, 1.0, 0.0, 1.0)
-                } else {
-                    Color::new(0.0, 0.0, 0.0, 1.0)
-                };
+    // Мелкий масштаб: каждая клетка закрашивается цветом своего вида состояния.
+    // Если в пиксель попадает несколько клеток, его цвет - среднее их цветов.
+    fn draw_overview(&self, ca: &CellularAutomation, ((x0, y0), (x1, y1)): Region) {
+        if x1 < x0 || y1 < y0 {
+            return;
+        }
 
-                draw_line(
-                    coords.0 + self.cell_size.0 / 2.0,
-                    coords.1 + self.cell_size.1 / 8.0,
-                    coords.0 + self.cell_size.0 / 2.0 - self.cell_size.0 / 4.0,
-                    coords.1 + self.cell_size.1 * 7.0 / 8.0,
-                    self.cell_size.0 / 10.0,
-                    c,
-                );
+        // Сколько клеток по каждой оси попадает в один пиксель текстуры
+        let block = (1.0 / self.cell_size.0.min(self.cell_size.1)).ceil() as i64;
+
+        // Группы клеток привязаны к координатам поля, а не к краю экрана,
+        // чтобы картинка не мерцала при перемещении
+        let origin = (x0.div_euclid(block) * block, y0.div_euclid(block) * block);
+
+        let mut overview = self.overview.borrow_mut();
+        let (texture, (width, height)) =
+            overview.get(self, ca, ((x0, y0), (x1, y1)), origin, block);
+
+        draw_texture_ex(
+            texture,
+            origin.0 as f32 * self.cell_size.0 + self.offset.0,
+            origin.1 as f32 * self.cell_size.1 + self.offset.1,
+            WHITE,
+            DrawTextureParams {
+                dest_size: Some(vec2(
+                    (width as i64 * block) as f32 * self.cell_size.0,
+                    (height as i64 * block) as f32 * self.cell_size.1,
+                )),
+                ..Default::default()
+            },
+        );
+    }
+}
 
-                draw_line(
-                    coords.0 + self.cell_size.0 / 2.0,
-                    coords.1 + self.cell_size.1 / 8.0,
-                    coords.0 + self.cell_size.0 / 2.0 + self.cell_size.0 / 4.0,
-                    coords.1 + self.cell_size.1 * 7.0 / 8.0,
-                    self.cell_size.0 / 10.0,
-                    c,
-                );
+// Область, начало групп клеток, размер группы и тема
+type FlatView = (Region, (i64, i64), i64, Theme);
+
+// Текстура из flat_image. Изображение строится заново при изменении области, групп клеток
+// или темы, а при изменении поля - не чаще FLAT_REFRESH, поэтому ни на неподвижном поле,
+// ни во время расчёта клетки не обходятся каждый кадр.
+#[derive(Default)]
+struct FlatTexture {
+    texture: Option<Texture2D>,
+    view: Option<FlatView>,
+    revision: u64,
+    built: Option<Instant>,
+    size: (usize, usize),
+}
 
-                draw_circle(
-                    coords.0 + self.cell_size.0 / 2.0,
-                    coords.1 + self.cell_size.1 / 2.0 + self.cell_size.1 / 4.0,
-                    self.cell_size.0 / 8.0,
-                    Color::new(1.0, 0.0, 0.0, 1.0),
-                );
-            }
+impl FlatTexture {
+    // Текстура и её размер в пикселях
+    fn get(
+        &mut self,
+        painter: &CellularAutomatonPainter,
+        ca: &CellularAutomation,
+        region: Region,
+        origin: (i64, i64),
+        block: i64,
+    ) -> (Texture2D, (usize, usize)) {
+        let view = (region, origin, block, painter.theme);
+
+        let outdated = self.view != Some(view)
+            || self.revision != painter.revision
+                && self
+                    .built
+                    .is_none_or(|built| built.elapsed() >= FLAT_REFRESH);
+
+        if outdated {
+            let image = flat_image(
+                ca,
+                region,
+                origin,
+                block,
+                painter.field_bounds(ca),
+                &painter.theme,
+            );
 
-            State::SpecialTransmitting {
-                dir: Direction::Left,
-                signal,
-            } => {
-                let c = if signal {
-                    Color::new(0.0, 1.0, 0.0, 1.0)
-                } else {
-                    Color::new(0.0, 0.0, 0.0, 1.0)
-                };
+            upload(&mut self.texture, &image);
 
-                draw_line(
-                    coords.0 + self.cell_size.0 / 8.0,
-                    coords.1 + self.cell_size.1 / 2.0,
-                    coords.0 + self.cell_size.0 * 7.0 / 8.0,
-                    coords.1 + self.cell_size.1 / 2.0 - self.cell_size.1 / 4.0,
-                    self.cell_size.0 / 10.0,
-                    c,
-                );
+            self.size = (image.width(), image.height());
+            self.view = Some(view);
+            self.revision = painter.revision;
+            self.built = Some(Instant::now());
+        }
 
-                draw_line(
-                    coords.0 + self.cell_size.0 / 8.0,
-                    coords.1 + self.cell_size.1 / 2.0,
-                    coords.0 + self.cell_size.0 * 7.0 / 8.0,
-                    coords.1 + self.cell_size.1 / 2.0 + self.cell_size.1 / 4.0,
-                    self.cell_size.0 / 10.0,
-                    c,
-                );
+        (self.texture.unwrap(), self.size)
+    }
+}
 
-                draw_circle(
-                    coords.0 + self.cell_size.0 / 2.0 + self.cell_size.0 / 4.0,
-                    coords.1 + self.cell_size.1 / 2.0,
-                    self.cell_size.0 / 8.0,
-                    Color::new(1.0, 0.0, 0.0, 1.0),
-                );
-            }
+impl Drop for FlatTexture {
+    fn drop(&mut self) {
+        if let Some(texture) = self.texture {
+            texture.delete();
+        }
+    }
+}
 
-            State::SpecialTransmitting {
-                dir: Direction::Down,
-                signal,
-            } => {
-                let c = if signal {
-                    Color::new(0.0, 1.0, 0.0, 1.0)
-                } else {
-                    Color::new(0.0, 0.0, 0.0, 1.0)
-                };
+// Изображение клеток области, в котором каждый пиксель - группа block x block клеток,
+// отсчитываемых от клетки origin. Клетки закрашиваются цветом вида состояния, цвет группы -
+// среднее цветов её непустых клеток, а непрозрачность - доля непустых клеток в группе,
+// чтобы редкие клетки не выглядели сплошной заливкой. Группы на краю области
+// учитываются целиком, иначе их цвет менялся бы при перемещении камеры.
+// Обходятся только клетки внутри прямоугольника bounds с непустыми клетками поля:
+// при сильном отдалении область на экране намного больше самого узора.
+fn flat_image(
+    ca: &CellularAutomation,
+    (_, (x1, y1)): Region,
+    (ox, oy): (i64, i64),
+    block: i64,
+    bounds: Option<Region>,
+    theme: &Theme,
+) -> Image {
+    let (width, height) = ((x1 - ox) / block + 1, (y1 - oy) / block + 1);
+
+    // Сумма цветов и количество непустых клеток в каждой группе
+    let mut sums = vec![(Color::new(0.0, 0.0, 0.0, 0.0), 0u32); (width * height) as usize];
+
+    let ((bx0, by0), (bx1, by1)) = bounds.unwrap_or(((0, 0), (-1, -1)));
+
+    for y in oy.max(by0)..(oy + height * block).min(by1 + 1) {
+        for x in ox.max(bx0)..(ox + width * block).min(bx1 + 1) {
+            let Some(color) = ca
+                .try_get_cell((x, y))
+                .and_then(|state| flat_color(state, theme))
+            else {
+                continue;
+            };
+
+            let (sum, count) = &mut sums[((y - oy) / block * width + (x - ox) / block) as usize];
+
+            sum.r += color.r;
+            sum.g += color.g;
+            sum.b += color.b;
+            *count += 1;
+        }
+    }
 
-                draw_line(
-                    coords.0 + self.cell_size.0 / 2.0,
-                    coords.1 + self.cell_size.1 - self.cell_size.1 / 8.0,
-                    coords.0 + self.cell_size.0 / 2.0 - self.cell_size.0 / 4.0,
-                    coords.1 + self.cell_size.1 - self.cell_size.1 * 7.0 / 8.0,
-                    self.cell_size.0 / 10.0,
-                    c,
-                );
+    let mut image =
+        Image::gen_image_color(width as u16, height as u16, Color::new(0.0, 0.0, 0.0, 0.0));
 
-                draw_line(
-                    coords.0 + self.cell_size.0 / 2.0,
-                    coords.1 + self.cell_size.1 - self.cell_size.1 / 8.0,
-                    coords.0 + self.cell_size.0 / 2.0 + self.cell_size.0 / 4.0,
-                    coords.1 + self.cell_size.1 - self.cell_size.1 * 7.0 / 8.0,
-                    self.cell_size.0 / 10.0,
-                    c,
-                );
-                draw_circle(
-                    coords.0 + self.cell_size.0 / 2.0,
-                    coords.1 + self.cell_size.1 / 2.0 - self.cell_size.1 / 4.0,
-                    self.cell_size.0 / 8.0,
-                    Color::new(1.0, 0.0, 0.0, 1.0),
-                );
+    let area = (block * block) as f32;
+
+    for (i, (sum, count)) in sums.into_iter().enumerate() {
+        if count > 0 {
+            let n = count as f32;
+
+            image.set_pixel(
+                (i as i64 % width) as u32,
+                (i as i64 / width) as u32,
+                Color::new(sum.r / n, sum.g / n, sum.b / n, n / area),
+            );
+        }
+    }
+
+    image
+}
+
+// Загрузка изображения в текстуру. Текстура пересоздаётся только при изменении размера.
+fn upload(slot: &mut Option<Texture2D>, image: &Image) -> Texture2D {
+    match *slot {
+        Some(texture)
+            if texture.width() as usize == image.width()
+                && texture.height() as usize == image.height() =>
+        {
+            texture.update(image);
+            texture
+        }
+        _ => {
+            if let Some(texture) = slot.take() {
+                texture.delete();
             }
 
-            State::SpecialTransmitting {
-                dir: Direction::Right,
-                signal,
-            } => {
-                let c = if signal {
-                    Color::new(0.0, 1.0, 0.0, 1.0)
-                } else {
-                    Color::new(0.0, 0.0, 0.0, 1.0)
-                };
+            let texture = Texture2D::from_image(image);
+            texture.set_filter(FilterMode::Nearest);
+            *slot = Some(texture);
+            texture
+        }
+    }
+}
 
-                draw_line(
-                    coords.0 + self.cell_size.0 - self.cell_size.0 / 8.0,
-                    coords.1 + self.cell_size.1 / 2.0,
-                    coords.0 + self.cell_size.0 - self.cell_size.0 * 7.0 / 8.0,
-                    coords.1 + self.cell_size.1 / 2.0 - self.cell_size.1 / 4.0,
-                    self.cell_size.0 / 10.0,
-                    c,
-                );
+// Цвет вида состояния для мелкого масштаба. Чувствительные клетки светлее (на изображении
+// они состоят из колец с промежутками).
+fn flat_color(state: &State, theme: &Theme) -> Option<Color> {
+    let rgb = match *state {
+        State::Unexcitable => return None,
+        State::NormalTransmitting { signal: true, .. }
+        | State::SpecialTransmitting { signal: true, .. }
+        | State::Confluence { new: true, .. }
+        | State::Confluence { old: true, .. } => theme.signal,
+        State::Sensitive { .. } => {
+            let (off, background) = (color(theme.sensitive), color(theme.background));
+
+            return Some(Color::new(
+                (off.r + background.r) / 2.0,
+                (off.g + background.g) / 2.0,
+                (off.b + background.b) / 2.0,
+                1.0,
+            ));
+        }
+        State::NormalTransmitting { .. } => theme.normal,
+        State::SpecialTransmitting { .. } => theme.special,
+        State::Confluence { .. } => theme.confluence,
+    };
 
-                draw_line(
-                    coords.0 + self.cell_size.0 - self.cell_size.0 / 8.0,
-                    coords.1 + self.cell_size.1 / 2.0,
-                    coords.0 + self.cell_size.0 - self.cell_size.0 * 7.0 / 8.0,
-                    coords.1 + self.cell_size.1 / 2.0 + self.cell_size.1 / 4.0,
-                    self.cell_size.0 / 10.0,
-                    c,
-                );
+    Some(color(rgb))
+}
+
+// Отрисовка прямоугольника поля так, что его левый верхний угол находится в точке (0, 0)
+pub fn draw_region(
+    canvas: &mut impl Canvas,
+    ca: &CellularAutomation,
+    ((x0, y0), (x1, y1)): Region,
+    cell_size: (f32, f32),
+    grid: bool,
+    theme: &Theme,
+) {
+    let (cx, cy) = cell_size;
+    let (width, height) = ((x1 - x0 + 1) as f32 * cx, (y1 - y0 + 1) as f32 * cy);
+
+    if grid {
+        for i in 0..=(x1 - x0 + 1) {
+            let x = i as f32 * cx;
+
+            canvas.line(x, 0.0, x, height, cx / 20.0, color(theme.grid));
+        }
+
+        for i in 0..=(y1 - y0 + 1) {
+            let y = i as f32 * cy;
+
+            canvas.line(0.0, y, width, y, cy / 20.0, color(theme.grid));
+        }
+    }
 
-                draw_circle(
-                    coords.0 + self.cell_size.0 / 2.0 - self.cell_size.0 / 4.0,
-                    coords.1 + self.cell_size.1 / 2.0,
-                    self.cell_size.0 / 8.0,
-                    Color::new(1.0, 0.0, 0.0, 1.0),
+    for y in y0..=y1 {
+        for x in x0..=x1 {
+            if let Some(state) = ca.try_get_cell((x, y)) {
+                draw_state(
+                    canvas,
+                    ((x - x0) as f32 * cx, (y - y0) as f32 * cy),
+                    cell_size,
+                    state,
+                    theme,
                 );
             }
+        }
+    }
+}
 
-            State::Confluence { new, old } => {
-                draw_rectangle(
-                    coords.0,
-                    coords.1,
-                    self.cell_size.0,
-                    self.cell_size.1,
-                    if old {
-                        Color::new(0.0, 1.0, 0.0, 1.0)
-                    } else {
-                        Color::new(0.0, 0.0, 0.0, 1.0)
-                    },
-                );
+pub fn draw_state(
+    canvas: &mut impl Canvas,
+    coords: (f32, f32),
+    cell_size: (f32, f32),
+    state: &State,
+    theme: &Theme,
+) {
+    let paint = |signal: bool, off: [u8; 3]| color(if signal { theme.signal } else { off });
+
+    match *state {
+        State::Unexcitable => {}
+
+        State::Sensitive { mut value } => {
+            let mut signals: Vec<bool> = vec![];
+
+            let mut count = 0;
+            while value & 0b10000000 != 0b10000000 {
+                value <<= 1;
+                count += 1;
+            }
+
+            for _ in 0..(8 - count) {
+                value <<= 1;
+                signals.push(value & 0b10000000 == 0b10000000);
+            }
 
-                draw_rectangle(
-                    coords.0 + self.cell_size.0 / 4.0,
-                    coords.1 + self.cell_size.1 / 4.0,
-                    self.cell_size.0 / 2.0,
-                    self.cell_size.1 / 2.0,
-                    if new {
-                        Color::new(0.0, 1.0, 0.0, 1.0)
+            let (cx, cy) = (coords.0 + cell_size.0 / 2.0, coords.1 + cell_size.1 / 2.0);
+            for (v, &k) in signals.into_iter().zip([1.0, 0.75, 0.5, 0.25].iter()) {
+                canvas.circle(
+                    cx,
+                    cy,
+                    cell_size.0 / 1.9 * k,
+                    if v {
+                        color(theme.signal)
                     } else {
-                        Color::new(0.0, 0.0, 0.0, 1.0)
+                        color(theme.background)
                     },
                 );
+                canvas.circle(cx, cy, cell_size.0 / 2.0 * k, paint(v, theme.sensitive));
             }
         }
-    }
 
-    pub fn draw(&self) {
-        let (cx, cy) = self.cell_size;
+        State::NormalTransmitting {
+            dir: Direction::Up,
+            signal,
+        } => {
+            let c = paint(signal, theme.normal);
+
+            canvas.line(
+                coords.0 + cell_size.0 / 2.0,
+                coords.1 + cell_size.1 / 8.0,
+                coords.0 + cell_size.0 / 2.0 - cell_size.0 / 4.0,
+                coords.1 + cell_size.1 * 7.0 / 8.0,
+                cell_size.0 / 10.0,
+                c,
+            );
 
-        let (mut x, mut y) = (0.0, 0.0);
-        while x < screen_width() {
-            draw_line(
-                x,
-                0.0,
-                x,
-                screen_height(),
-                cx / 20.0,
-                Color::new(0.0, 0.0, 0.0, 1.0),
+            canvas.line(
+                coords.0 + cell_size.0 / 2.0,
+                coords.1 + cell_size.1 / 8.0,
+                coords.0 + cell_size.0 / 2.0 + cell_size.0 / 4.0,
+                coords.1 + cell_size.1 * 7.0 / 8.0,
+                cell_size.0 / 10.0,
+                c,
             );
-            x += cx;
         }
 
-        while y < screen_height() {
-            draw_line(
-                0.0,
-                y,
-                screen_width(),
-                y,
-                cy / 20.0,
-                Color::new(0.0, 0.0, 0.0, 1.0),
+        State::NormalTransmitting {
+            dir: Direction::Left,
+            signal,
+        } => {
+            let c = paint(signal, theme.normal);
+
+            canvas.line(
+                coords.0 + cell_size.0 / 8.0,
+                coords.1 + cell_size.1 / 2.0,
+                coords.0 + cell_size.0 * 7.0 / 8.0,
+                coords.1 + cell_size.1 / 2.0 - cell_size.1 / 4.0,
+                cell_size.0 / 10.0,
+                c,
+            );
+
+            canvas.line(
+                coords.0 + cell_size.0 / 8.0,
+                coords.1 + cell_size.1 / 2.0,
+                coords.0 + cell_size.0 * 7.0 / 8.0,
+                coords.1 + cell_size.1 / 2.0 + cell_size.1 / 4.0,
+                cell_size.0 / 10.0,
+                c,
             );
-            y += cy;
         }
 
-        let ca = self.cellular_automaton.read().unwrap();
+        State::NormalTransmitting {
+            dir: Direction::Down,
+            signal,
+        } => {
+            let c = paint(signal, theme.normal);
+
+            canvas.line(
+                coords.0 + cell_size.0 / 2.0,
+                coords.1 + cell_size.1 - cell_size.1 / 8.0,
+                coords.0 + cell_size.0 / 2.0 - cell_size.0 / 4.0,
+                coords.1 + cell_size.1 - cell_size.1 * 7.0 / 8.0,
+                cell_size.0 / 10.0,
+                c,
+            );
 
-        for y in (-ca.center.1)..(ca.field.len() as i64 - ca.center.1) {
-            for x in (-ca.center.0)..(ca.field[0].len() as i64 - ca.center.0) {
-                let state = ca.get_cell((x, y));
-                let (x, y) = (x as f32, y as f32);
+            canvas.line(
+                coords.0 + cell_size.0 / 2.0,
+                coords.1 + cell_size.1 - cell_size.1 / 8.0,
+                coords.0 + cell_size.0 / 2.0 + cell_size.0 / 4.0,
+                coords.1 + cell_size.1 - cell_size.1 * 7.0 / 8.0,
+                cell_size.0 / 10.0,
+                c,
+            );
+        }
 
-                self.draw_state(
-                    (
-                        self.cell_size.0 * x + self.offset.0,
-                        self.cell_size.1 * y + self.offset.1,
-                    ),
-                    state,
-                );
-            }
+        State::NormalTransmitting {
+            dir: Direction::Right,
+            signal,
+        } => {
+            let c = paint(signal, theme.normal);
+
+            canvas.line(
+                coords.0 + cell_size.0 - cell_size.0 / 8.0,
+                coords.1 + cell_size.1 / 2.0,
+                coords.0 + cell_size.0 - cell_size.0 * 7.0 / 8.0,
+                coords.1 + cell_size.1 / 2.0 - cell_size.1 / 4.0,
+                cell_size.0 / 10.0,
+                c,
+            );
+
+            canvas.line(
+                coords.0 + cell_size.0 - cell_size.0 / 8.0,
+                coords.1 + cell_size.1 / 2.0,
+                coords.0 + cell_size.0 - cell_size.0 * 7.0 / 8.0,
+                coords.1 + cell_size.1 / 2.0 + cell_size.1 / 4.0,
+                cell_size.0 / 10.0,
+                c,
+            );
+        }
+
+        State::SpecialTransmitting {
+            dir: Direction::Up,
+            signal,
+        } => {
+            let c = paint(signal, theme.special);
+
+            canvas.line(
+                coords.0 + cell_size.0 / 2.0,
+                coords.1 + cell_size.1 / 8.0,
+                coords.0 + cell_size.0 / 2.0 - cell_size.0 / 4.0,
+                coords.1 + cell_size.1 * 7.0 / 8.0,
+                cell_size.0 / 10.0,
+                c,
+            );
+
+            canvas.line(
+                coords.0 + cell_size.0 / 2.0,
+                coords.1 + cell_size.1 / 8.0,
+                coords.0 + cell_size.0 / 2.0 + cell_size.0 / 4.0,
+                coords.1 + cell_size.1 * 7.0 / 8.0,
+                cell_size.0 / 10.0,
+                c,
+            );
+
+            canvas.circle(
+                coords.0 + cell_size.0 / 2.0,
+                coords.1 + cell_size.1 / 2.0 + cell_size.1 / 4.0,
+                cell_size.0 / 8.0,
+                color(theme.special_mark),
+            );
+        }
+
+        State::SpecialTransmitting {
+            dir: Direction::Left,
+            signal,
+        } => {
+            let c = paint(signal, theme.special);
+
+            canvas.line(
+                coords.0 + cell_size.0 / 8.0,
+                coords.1 + cell_size.1 / 2.0,
+                coords.0 + cell_size.0 * 7.0 / 8.0,
+                coords.1 + cell_size.1 / 2.0 - cell_size.1 / 4.0,
+                cell_size.0 / 10.0,
+                c,
+            );
+
+            canvas.line(
+                coords.0 + cell_size.0 / 8.0,
+                coords.1 + cell_size.1 / 2.0,
+                coords.0 + cell_size.0 * 7.0 / 8.0,
+                coords.1 + cell_size.1 / 2.0 + cell_size.1 / 4.0,
+                cell_size.0 / 10.0,
+                c,
+            );
+
+            canvas.circle(
+                coords.0 + cell_size.0 / 2.0 + cell_size.0 / 4.0,
+                coords.1 + cell_size.1 / 2.0,
+                cell_size.0 / 8.0,
+                color(theme.special_mark),
+            );
+        }
+
+        State::SpecialTransmitting {
+            dir: Direction::Down,
+            signal,
+        } => {
+            let c = paint(signal, theme.special);
+
+            canvas.line(
+                coords.0 + cell_size.0 / 2.0,
+                coords.1 + cell_size.1 - cell_size.1 / 8.0,
+                coords.0 + cell_size.0 / 2.0 - cell_size.0 / 4.0,
+                coords.1 + cell_size.1 - cell_size.1 * 7.0 / 8.0,
+                cell_size.0 / 10.0,
+                c,
+            );
+
+            canvas.line(
+                coords.0 + cell_size.0 / 2.0,
+                coords.1 + cell_size.1 - cell_size.1 / 8.0,
+                coords.0 + cell_size.0 / 2.0 + cell_size.0 / 4.0,
+                coords.1 + cell_size.1 - cell_size.1 * 7.0 / 8.0,
+                cell_size.0 / 10.0,
+                c,
+            );
+            canvas.circle(
+                coords.0 + cell_size.0 / 2.0,
+                coords.1 + cell_size.1 / 2.0 - cell_size.1 / 4.0,
+                cell_size.0 / 8.0,
+                color(theme.special_mark),
+            );
+        }
+
+        State::SpecialTransmitting {
+            dir: Direction::Right,
+            signal,
+        } => {
+            let c = paint(signal, theme.special);
+
+            canvas.line(
+                coords.0 + cell_size.0 - cell_size.0 / 8.0,
+                coords.1 + cell_size.1 / 2.0,
+                coords.0 + cell_size.0 - cell_size.0 * 7.0 / 8.0,
+                coords.1 + cell_size.1 / 2.0 - cell_size.1 / 4.0,
+                cell_size.0 / 10.0,
+                c,
+            );
+
+            canvas.line(
+                coords.0 + cell_size.0 - cell_size.0 / 8.0,
+                coords.1 + cell_size.1 / 2.0,
+                coords.0 + cell_size.0 - cell_size.0 * 7.0 / 8.0,
+                coords.1 + cell_size.1 / 2.0 + cell_size.1 / 4.0,
+                cell_size.0 / 10.0,
+                c,
+            );
+
+            canvas.circle(
+                coords.0 + cell_size.0 / 2.0 - cell_size.0 / 4.0,
+                coords.1 + cell_size.1 / 2.0,
+                cell_size.0 / 8.0,
+                color(theme.special_mark),
+            );
+        }
+
+        State::Confluence { new, old } => {
+            canvas.rectangle(
+                coords.0,
+                coords.1,
+                cell_size.0,
+                cell_size.1,
+                paint(old, theme.confluence),
+            );
+
+            canvas.rectangle(
+                coords.0 + cell_size.0 / 4.0,
+                coords.1 + cell_size.1 / 4.0,
+                cell_size.0 / 2.0,
+                cell_size.1 / 2.0,
+                paint(new, theme.confluence),
+            );
         }
     }
 }
@@ -0,0 +1,125 @@
+use super::Canvas;
+use macroquad::prelude::Color;
+
+// Изображение в памяти (RGBA, по 4 байта на пиксель), в которое можно рисовать без окна.
+// Края линий и кругов сглаживаются по расстоянию от центра пикселя до фигуры.
+pub struct RasterCanvas {
+    pub width: u32,
+    pub height: u32,
+    pub pixels: Vec<u8>,
+
+    // Углы области, за пределы которой фигуры не рисуются (по умолчанию всё изображение)
+    clip: ((u32, u32), (u32, u32)),
+}
+
+impl RasterCanvas {
+    pub fn new(width: u32, height: u32, background: Color) -> Self {
+        let rgba: [u8; 4] = background.into();
+
+        Self {
+            width,
+            height,
+            pixels: rgba.repeat(width as usize * height as usize),
+            clip: ((0, 0), (width, height)),
+        }
+    }
+
+    // Ограничение рисования прямоугольником (x, y, ширина, высота), None - всё изображение
+    pub fn set_clip(&mut self, clip: Option<(u32, u32, u32, u32)>) {
+        self.clip = match clip {
+            Some((x, y, w, h)) => (
+                (x.min(self.width), y.min(self.height)),
+                ((x + w).min(self.width), (y + h).min(self.height)),
+            ),
+            None => ((0, 0), (self.width, self.height)),
+        };
+    }
+
+    // Пиксели, которые может задеть фигура с такими границами
+    fn pixels_around(
+        &self,
+        (x0, y0): (f32, f32),
+        (x1, y1): (f32, f32),
+    ) -> impl Iterator<Item = (u32, u32)> {
+        let ((cx0, cy0), (cx1, cy1)) = self.clip;
+
+        let clamp_x = |x: f32| (x.max(0.0) as u32).clamp(cx0, cx1);
+        let clamp_y = |y: f32| (y.max(0.0) as u32).clamp(cy0, cy1);
+
+        let (px0, px1) = (clamp_x(x0.floor()), clamp_x(x1.ceil() + 1.0));
+        let (py0, py1) = (clamp_y(y0.floor()), clamp_y(y1.ceil() + 1.0));
+
+        (py0..py1).flat_map(move |y| (px0..px1).map(move |x| (x, y)))
+    }
+
+    fn blend(&mut self, (x, y): (u32, u32), color: Color, coverage: f32) {
+        let alpha = color.a * coverage.clamp(0.0, 1.0);
+
+        if alpha <= 0.0 {
+            return;
+        }
+
+        let i = (y as usize * self.width as usize + x as usize) * 4;
+        let pixel = &mut self.pixels[i..i + 4];
+
+        for (channel, value) in pixel.iter_mut().zip([color.r, color.g, color.b]) {
+            *channel = (value * 255.0 * alpha + *channel as f32 * (1.0 - alpha)).round() as u8;
+        }
+
+        pixel[3] = (255.0 * alpha + pixel[3] as f32 * (1.0 - alpha)).round() as u8;
+    }
+}
+
+impl Canvas for RasterCanvas {
+    fn line(&mut self, x1: f32, y1: f32, x2: f32, y2: f32, thickness: f32, color: Color) {
+        let (dx, dy) = (x2 - x1, y2 - y1);
+        let length = (dx * dx + dy * dy).sqrt();
+
+        if length == 0.0 {
+            return;
+        }
+
+        let half = thickness / 2.0;
+        let pixels: Vec<_> = self
+            .pixels_around(
+                (x1.min(x2) - half, y1.min(y2) - half),
+                (x1.max(x2) + half, y1.max(y2) + half),
+            )
+            .collect();
+
+        for (x, y) in pixels {
+            let (cx, cy) = (x as f32 + 0.5 - x1, y as f32 + 0.5 - y1);
+
+            // Расстояние вдоль линии от её начала и расстояние до самой линии
+            let along = (cx * dx + cy * dy) / length;
+            let across = (cx * dy - cy * dx).abs() / length;
+
+            let coverage = (half + 0.5 - across).clamp(0.0, thickness.min(1.0))
+                * (along + 0.5).min(length - along + 0.5).clamp(0.0, 1.0);
+
+            self.blend((x, y), color, coverage);
+        }
+    }
+
+    fn circle(&mut self, x: f32, y: f32, r: f32, color: Color) {
+        let pixels: Vec<_> = self.pixels_around((x - r, y - r), (x + r, y + r)).collect();
+
+        for (px, py) in pixels {
+            let (dx, dy) = (px as f32 + 0.5 - x, py as f32 + 0.5 - y);
+
+            self.blend((px, py), color, r + 0.5 - (dx * dx + dy * dy).sqrt());
+        }
+    }
+
+    fn rectangle(&mut self, x: f32, y: f32, w: f32, h: f32, color: Color) {
+        let pixels: Vec<_> = self.pixels_around((x, y), (x + w, y + h)).collect();
+
+        for (px, py) in pixels {
+            // Доля пикселя, покрытая прямоугольником
+            let cover_x = ((x + w).min(px as f32 + 1.0) - x.max(px as f32)).max(0.0);
+            let cover_y = ((y + h).min(py as f32 + 1.0) - y.max(py as f32)).max(0.0);
+
+            self.blend((px, py), color, cover_x * cover_y);
+        }
+    }
+}
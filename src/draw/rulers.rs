@@ -0,0 +1,81 @@
+use super::{color, CellularAutomatonPainter, MIN_CELL_SIZE};
+use macroquad::prelude::draw_line;
+use macroquad::shapes::draw_rectangle;
+use macroquad::text::draw_text;
+use macroquad::window::{screen_height, screen_width};
+
+// Линейки с координатами клеток вдоль верхнего и левого краёв окна
+
+// Высота верхней и ширина левой линейки (в левую должны помещаться числа вроде -10000)
+pub const TOP_RULER: f32 = 18.0;
+pub const LEFT_RULER: f32 = 48.0;
+
+const FONT_SIZE: f32 = 16.0;
+
+// Наименьшее расстояние между подписями в пикселях
+const LABEL_SPACING: f32 = 60.0;
+
+// Шаг подписей в клетках: наименьшее из 1, 2, 5, 10, 20, 50, ..., при котором подписи не слипаются.
+// Размер клетки ограничен снизу, иначе при нулевом размере цикл не закончится.
+fn step(cell_size: f32) -> i64 {
+    let cell_size = cell_size.max(MIN_CELL_SIZE);
+    let mut power = 1;
+
+    loop {
+        for k in [1, 2, 5] {
+            if (k * power) as f32 * cell_size >= LABEL_SPACING {
+                return k * power;
+            }
+        }
+
+        power *= 10;
+    }
+}
+
+impl CellularAutomatonPainter {
+    pub fn draw_rulers(&self) {
+        let (cx, cy) = self.cell_size;
+        let (background, line, text) = (
+            color(self.theme.background),
+            color(self.theme.grid),
+            color(self.theme.normal),
+        );
+
+        draw_rectangle(0.0, 0.0, screen_width(), TOP_RULER, background);
+        draw_rectangle(0.0, 0.0, LEFT_RULER, screen_height(), background);
+        draw_line(0.0, TOP_RULER, screen_width(), TOP_RULER, 1.0, line);
+        draw_line(LEFT_RULER, 0.0, LEFT_RULER, screen_height(), 1.0, line);
+
+        let ((x0, y0), (x1, y1)) = self.visible_region();
+
+        // Отметка стоит на левой (верхней) границе клетки, подпись - справа (снизу) от неё
+        let step_x = step(cx);
+        let mut x = x0.div_euclid(step_x) * step_x;
+        while x <= x1 {
+            let px = x as f32 * cx + self.offset.0;
+
+            if px >= LEFT_RULER {
+                draw_line(px, 0.0, px, TOP_RULER, 1.0, line);
+                draw_text(&x.to_string(), px + 3.0, TOP_RULER - 4.0, FONT_SIZE, text);
+            }
+
+            x += step_x;
+        }
+
+        let step_y = step(cy);
+        let mut y = y0.div_euclid(step_y) * step_y;
+        while y <= y1 {
+            let py = y as f32 * cy + self.offset.1;
+
+            if py >= TOP_RULER {
+                draw_line(0.0, py, LEFT_RULER, py, 1.0, line);
+                draw_text(&y.to_string(), 3.0, py + FONT_SIZE - 2.0, FONT_SIZE, text);
+            }
+
+            y += step_y;
+        }
+
+        // Угол, где линейки пересекаются
+        draw_rectangle(0.0, 0.0, LEFT_RULER, TOP_RULER, background);
+    }
+}
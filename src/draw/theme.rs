@@ -0,0 +1,97 @@
+use macroquad::prelude::Color;
+use serde::{Deserialize, Serialize};
+
+// Цвета, которыми рисуется поле. Хранятся в sRGB по байту на канал,
+// чтобы их было удобно править в файле настроек.
+#[derive(Serialize, Deserialize, Debug, Clone, Copy, PartialEq)]
+#[serde(default)]
+pub struct Theme {
+    pub background: [u8; 3],
+    pub grid: [u8; 3],
+
+    // Состояния без сигнала
+    pub sensitive: [u8; 3],
+    pub normal: [u8; 3],
+    pub special: [u8; 3],
+    pub confluence: [u8; 3],
+
+    // Точка, которой специальные передающие отличаются от обычных
+    pub special_mark: [u8; 3],
+
+    // Части клеток, по которым идёт сигнал
+    pub signal: [u8; 3],
+}
+
+impl Theme {
+    pub const LIGHT: Theme = Theme {
+        background: [255, 255, 255],
+        grid: [0, 0, 0],
+        sensitive: [0, 0, 0],
+        normal: [0, 0, 0],
+        special: [0, 0, 0],
+        confluence: [0, 0, 0],
+        special_mark: [255, 0, 0],
+        signal: [0, 255, 0],
+    };
+
+    pub const DARK: Theme = Theme {
+        background: [24, 24, 28],
+        grid: [70, 70, 78],
+        sensitive: [170, 170, 180],
+        normal: [220, 220, 230],
+        special: [220, 220, 230],
+        confluence: [150, 150, 230],
+        special_mark: [255, 90, 90],
+        signal: [80, 255, 80],
+    };
+
+    // Близко к цветам правила JvN29 в Golly: обычные передающие синие,
+    // специальные красные, конфлюентные жёлтые на чёрном фоне
+    pub const GOLLY: Theme = Theme {
+        background: [0, 0, 0],
+        grid: [48, 48, 48],
+        sensitive: [255, 160, 80],
+        normal: [106, 106, 255],
+        special: [255, 56, 56],
+        confluence: [255, 255, 0],
+        special_mark: [255, 191, 191],
+        signal: [255, 255, 255],
+    };
+
+    // Палитра Окабе-Ито, различимая при всех распространённых видах дальтонизма
+    pub const COLOR_BLIND: Theme = Theme {
+        background: [255, 255, 255],
+        grid: [160, 160, 160],
+        sensitive: [90, 90, 90],
+        normal: [0, 114, 178],
+        special: [204, 121, 167],
+        confluence: [0, 0, 0],
+        special_mark: [213, 94, 0],
+        signal: [230, 159, 0],
+    };
+
+    pub const PRESETS: [(&'static str, Theme); 4] = [
+        ("Светлая", Theme::LIGHT),
+        ("Тёмная", Theme::DARK),
+        ("Как в Golly", Theme::GOLLY),
+        ("Для дальтоников", Theme::COLOR_BLIND),
+    ];
+
+    // Название готовой темы, если текущая совпадает с одной из них
+    pub fn preset_name(&self) -> Option<&'static str> {
+        Theme::PRESETS
+            .iter()
+            .find(|(_, theme)| theme == self)
+            .map(|(name, _)| *name)
+    }
+}
+
+impl Default for Theme {
+    fn default() -> Self {
+        Theme::LIGHT
+    }
+}
+
+pub fn color([r, g, b]: [u8; 3]) -> Color {
+    Color::from_rgba(r, g, b, 255)
+}
@@ -0,0 +1,238 @@
+use super::{image_size, render, ExportError, ImageOptions};
+use crate::cellular_automaton::{CellularAutomation, Region};
+use std::fs::File;
+use std::io::BufWriter;
+use std::path::Path;
+
+#[derive(Debug, Clone, Copy)]
+pub struct AnimationOptions {
+    // Диапазон тактов (включительно) и шаг между кадрами
+    pub from: u64,
+    pub to: u64,
+    pub stride: u64,
+
+    // Задержка между кадрами в миллисекундах
+    pub delay: u16,
+
+    // Если область не указана, берётся область, покрывающая все кадры
+    pub image: ImageOptions,
+}
+
+impl Default for AnimationOptions {
+    fn default() -> Self {
+        Self {
+            from: 0,
+            to: 100,
+            stride: 1,
+            delay: 100,
+            image: ImageOptions::default(),
+        }
+    }
+}
+
+// В GIF задержка задаётся в сотых долях секунды: миллисекунды округляются до ближайших 10.
+// Нулевую задержку программы просмотра заменяют своей, поэтому она не меньше 1.
+fn gif_delay(delay: u16) -> u16 {
+    ((delay as u32 + 5) / 10).max(1) as u16
+}
+
+// Проверка диапазона тактов и число кадров
+fn frame_count(ca: &CellularAutomation, options: &AnimationOptions) -> Result<u64, ExportError> {
+    if options.stride == 0 {
+        return Err(ExportError::InvalidRange(
+            "шаг между кадрами должен быть больше нуля".to_string(),
+        ));
+    }
+
+    if options.from > options.to {
+        return Err(ExportError::InvalidRange(format!(
+            "начальный такт {} больше конечного {}",
+            options.from, options.to
+        )));
+    }
+
+    if options.from < ca.tact {
+        return Err(ExportError::InvalidRange(format!(
+            "начальный такт {} меньше текущего такта поля {}",
+            options.from, ca.tact
+        )));
+    }
+
+    Ok((options.to - options.from) / options.stride + 1)
+}
+
+// Обход состояний поля на тактах from, from + stride, ..., не больше to.
+// Шагает одна копия поля, так что в памяти одновременно находится только один кадр.
+// Исходный автомат не изменяется.
+fn for_each_frame(
+    ca: &CellularAutomation,
+    options: &AnimationOptions,
+    mut f: impl FnMut(&CellularAutomation) -> Result<(), ExportError>,
+) -> Result<(), ExportError> {
+    let mut ca = ca.clone();
+
+    while ca.tact < options.from {
+        ca.tact();
+    }
+
+    f(&ca)?;
+
+    while ca.tact + options.stride <= options.to {
+        for _ in 0..options.stride {
+            ca.tact();
+        }
+
+        f(&ca)?;
+    }
+
+    Ok(())
+}
+
+// Общая область всех кадров, чтобы изображение не прыгало от кадра к кадру.
+// Для неё поле проходится лишний раз, зато кадры не приходится хранить.
+fn common_region(
+    ca: &CellularAutomation,
+    options: &AnimationOptions,
+) -> Result<Region, ExportError> {
+    if let Some(region) = options.image.region {
+        return Ok(region);
+    }
+
+    let mut region: Option<Region> = None;
+
+    for_each_frame(ca, options, |frame| {
+        if let Some(((bx0, by0), (bx1, by1))) = frame.bounds() {
+            region = Some(match region {
+                Some(((ax0, ay0), (ax1, ay1))) => {
+                    ((ax0.min(bx0), ay0.min(by0)), (ax1.max(bx1), ay1.max(by1)))
+                }
+                None => ((bx0, by0), (bx1, by1)),
+            });
+        }
+
+        Ok(())
+    })?;
+
+    Ok(region.unwrap_or(((0, 0), (0, 0))))
+}
+
+// Настройки изображения кадров, их размер и число
+fn prepare(
+    ca: &CellularAutomation,
+    options: &AnimationOptions,
+) -> Result<(ImageOptions, (u32, u32), u64), ExportError> {
+    let count = frame_count(ca, options)?;
+    let region = common_region(ca, options)?;
+    let size = image_size(region, options.image.cell_size)?;
+
+    let image = ImageOptions {
+        region: Some(region),
+        ..options.image
+    };
+
+    Ok((image, size, count))
+}
+
+pub fn export_gif(
+    path: &Path,
+    ca: &CellularAutomation,
+    options: &AnimationOptions,
+) -> Result<(), ExportError> {
+    let (image, (width, height), _) = prepare(ca, options)?;
+
+    // Размеры кадра в GIF ограничены 16 битами
+    if width > u16::MAX as u32 || height > u16::MAX as u32 {
+        return Err(ExportError::TooLarge {
+            width: width as u64,
+            height: height as u64,
+        });
+    }
+
+    let mut encoder = gif::Encoder::new(
+        BufWriter::new(File::create(path)?),
+        width as u16,
+        height as u16,
+        &[],
+    )?;
+
+    encoder.set_repeat(gif::Repeat::Infinite)?;
+
+    for_each_frame(ca, options, |ca| {
+        let mut canvas = render(ca, &image)?;
+        let mut frame =
+            gif::Frame::from_rgba_speed(width as u16, height as u16, &mut canvas.pixels, 10);
+
+        frame.delay = gif_delay(options.delay);
+
+        encoder.write_frame(&frame)?;
+
+        Ok(())
+    })
+}
+
+pub fn export_apng(
+    path: &Path,
+    ca: &CellularAutomation,
+    options: &AnimationOptions,
+) -> Result<(), ExportError> {
+    let (image, (width, height), count) = prepare(ca, options)?;
+
+    let mut encoder = png::Encoder::new(BufWriter::new(File::create(path)?), width, height);
+
+    encoder.set_color(png::ColorType::Rgba);
+    encoder.set_depth(png::BitDepth::Eight);
+    encoder.set_animated(count as u32, 0)?;
+    encoder.set_frame_delay(options.delay, 1000)?;
+
+    let mut writer = encoder.write_header()?;
+
+    for_each_frame(ca, options, |ca| {
+        writer.write_image_data(&render(ca, &image)?.pixels)?;
+
+        Ok(())
+    })?;
+
+    writer.finish()?;
+
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::cellular_automaton::State;
+
+    #[test]
+    fn gif_frame_delay() {
+        let mut ca = CellularAutomation::new();
+        ca.set_cell((0, 0), State::Sensitive { value: 1 });
+
+        let path = std::env::temp_dir().join(format!("kavn-test-{}.gif", std::process::id()));
+
+        // Задержка 5 мс не превращается в нулевую, 15 мс округляется до 20, а не до 10
+        for (delay, expected) in [(5, 1), (15, 2), (100, 10), (u16::MAX, 6554)] {
+            let options = AnimationOptions {
+                from: 0,
+                to: 2,
+                delay,
+                ..AnimationOptions::default()
+            };
+
+            export_gif(&path, &ca, &options).unwrap();
+
+            let mut decoder = gif::DecodeOptions::new()
+                .read_info(File::open(&path).unwrap())
+                .unwrap();
+            let mut frames = 0;
+
+            while let Some(frame) = decoder.read_next_frame().unwrap() {
+                assert_eq!(frame.delay, expected, "задержка {} мс", delay);
+                frames += 1;
+            }
+
+            assert_eq!(frames, 3);
+        }
+
+        std::fs::remove_file(&path).unwrap();
+    }
+}
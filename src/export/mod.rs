@@ -0,0 +1,154 @@
+mod animation;
+mod svg;
+
+pub use animation::*;
+pub use svg::*;
+
+use crate::cellular_automaton::{CellularAutomation, Region};
+use crate::draw::{color, draw_region, RasterCanvas, Theme};
+use std::fmt::{Display, Formatter};
+use std::fs::File;
+use std::io::{self, BufWriter};
+use std::path::Path;
+
+// Ограничение на размер изображения, чтобы случайно не занять всю память
+const MAX_PIXELS: u64 = 1 << 28;
+
+#[derive(Debug)]
+pub enum ExportError {
+    Io(io::Error),
+    Png(png::EncodingError),
+    Gif(gif::EncodingError),
+    InvalidRange(String),
+    TooLarge { width: u64, height: u64 },
+}
+
+impl Display for ExportError {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        match self {
+            ExportError::Io(err) => write!(f, "Ошибка записи файла: {}", err),
+            ExportError::Png(err) => write!(f, "Ошибка создания PNG: {}", err),
+            ExportError::Gif(err) => write!(f, "Ошибка создания GIF: {}", err),
+            ExportError::InvalidRange(message) => {
+                write!(f, "Неверный диапазон тактов: {}", message)
+            }
+            ExportError::TooLarge { width, height } => write!(
+                f,
+                "Изображение {}x{} слишком большое, уменьшите область или размер клетки",
+                width, height
+            ),
+        }
+    }
+}
+
+impl From<io::Error> for ExportError {
+    fn from(err: io::Error) -> Self {
+        ExportError::Io(err)
+    }
+}
+
+impl From<png::EncodingError> for ExportError {
+    fn from(err: png::EncodingError) -> Self {
+        ExportError::Png(err)
+    }
+}
+
+impl From<gif::EncodingError> for ExportError {
+    fn from(err: gif::EncodingError) -> Self {
+        ExportError::Gif(err)
+    }
+}
+
+#[derive(Debug, Clone, Copy)]
+pub struct ImageOptions {
+    // Если область не указана, экспортируются все непустые клетки
+    pub region: Option<Region>,
+    pub cell_size: u32,
+    pub grid: bool,
+
+    // Подписи координат по краям (только для SVG)
+    pub labels: bool,
+
+    pub theme: Theme,
+}
+
+impl Default for ImageOptions {
+    fn default() -> Self {
+        Self {
+            region: None,
+            cell_size: 20,
+            grid: true,
+            labels: false,
+            theme: Theme::default(),
+        }
+    }
+}
+
+impl ImageOptions {
+    fn region_of(&self, ca: &CellularAutomation) -> Region {
+        self.region
+            .or_else(|| ca.bounds())
+            .unwrap_or(((0, 0), (0, 0)))
+    }
+}
+
+// Размер изображения области в пикселях
+fn image_size(region: Region, cell_size: u32) -> Result<(u32, u32), ExportError> {
+    let ((x0, y0), (x1, y1)) = region;
+
+    let cell_size = cell_size as u64;
+    let (width, height) = (
+        (x1 - x0 + 1) as u64 * cell_size,
+        (y1 - y0 + 1) as u64 * cell_size,
+    );
+
+    if width * height > MAX_PIXELS {
+        return Err(ExportError::TooLarge { width, height });
+    }
+
+    Ok((width as u32, height as u32))
+}
+
+// Отрисовка поля в изображение без открытия окна
+pub fn render(
+    ca: &CellularAutomation,
+    options: &ImageOptions,
+) -> Result<RasterCanvas, ExportError> {
+    let region = options.region_of(ca);
+    let (width, height) = image_size(region, options.cell_size)?;
+
+    let mut canvas = RasterCanvas::new(width, height, color(options.theme.background));
+    let cell_size = options.cell_size as f32;
+
+    draw_region(
+        &mut canvas,
+        ca,
+        region,
+        (cell_size, cell_size),
+        options.grid,
+        &options.theme,
+    );
+
+    Ok(canvas)
+}
+
+pub fn export_png(
+    path: &Path,
+    ca: &CellularAutomation,
+    options: &ImageOptions,
+) -> Result<(), ExportError> {
+    let image = render(ca, options)?;
+
+    let mut encoder = png::Encoder::new(
+        BufWriter::new(File::create(path)?),
+        image.width,
+        image.height,
+    );
+
+    encoder.set_color(png::ColorType::Rgba);
+    encoder.set_depth(png::BitDepth::Eight);
+
+    encoder.write_header()?.write_image_data(&image.pixels)?;
+
+    Ok(())
+}
@@ -0,0 +1,157 @@
+use super::{ExportError, ImageOptions};
+use crate::cellular_automaton::CellularAutomation;
+use crate::draw::{color, draw_region, Canvas};
+use macroquad::prelude::Color;
+use std::fs;
+use std::path::Path;
+
+// Векторное изображение: каждая фигура клетки становится отдельным элементом SVG
+pub struct SvgCanvas {
+    elements: Vec<String>,
+}
+
+impl SvgCanvas {
+    fn text(&mut self, x: f32, y: f32, size: f32, anchor: &str, text: &str) {
+        self.elements.push(format!(
+            r#"<text x="{}" y="{}" font-size="{}" font-family="sans-serif" text-anchor="{}" dominant-baseline="middle">{}</text>"#,
+            num(x),
+            num(y),
+            num(size),
+            anchor,
+            text
+        ));
+    }
+}
+
+impl Canvas for SvgCanvas {
+    fn line(&mut self, x1: f32, y1: f32, x2: f32, y2: f32, thickness: f32, color: Color) {
+        self.elements.push(format!(
+            r#"<line x1="{}" y1="{}" x2="{}" y2="{}" stroke-width="{}" stroke="{}"{}/>"#,
+            num(x1),
+            num(y1),
+            num(x2),
+            num(y2),
+            num(thickness),
+            rgb(color),
+            opacity("stroke-opacity", color)
+        ));
+    }
+
+    fn circle(&mut self, x: f32, y: f32, r: f32, color: Color) {
+        self.elements.push(format!(
+            r#"<circle cx="{}" cy="{}" r="{}" fill="{}"{}/>"#,
+            num(x),
+            num(y),
+            num(r),
+            rgb(color),
+            opacity("fill-opacity", color)
+        ));
+    }
+
+    fn rectangle(&mut self, x: f32, y: f32, w: f32, h: f32, color: Color) {
+        self.elements.push(format!(
+            r#"<rect x="{}" y="{}" width="{}" height="{}" fill="{}"{}/>"#,
+            num(x),
+            num(y),
+            num(w),
+            num(h),
+            rgb(color),
+            opacity("fill-opacity", color)
+        ));
+    }
+}
+
+pub fn render_svg(ca: &CellularAutomation, options: &ImageOptions) -> String {
+    let region = options.region_of(ca);
+    let ((x0, y0), (x1, y1)) = region;
+
+    let cell_size = options.cell_size as f32;
+    let (width, height) = (
+        (x1 - x0 + 1) as f32 * cell_size,
+        (y1 - y0 + 1) as f32 * cell_size,
+    );
+
+    // Для подписей координат слева и сверху оставляется поле в одну клетку
+    let margin = if options.labels { cell_size } else { 0.0 };
+
+    let mut canvas = SvgCanvas { elements: vec![] };
+
+    if options.labels {
+        let size = cell_size * 0.4;
+
+        for x in x0..=x1 {
+            let cx = margin + (x - x0) as f32 * cell_size + cell_size / 2.0;
+
+            canvas.text(cx, margin / 2.0, size, "middle", &x.to_string());
+        }
+
+        for y in y0..=y1 {
+            let cy = margin + (y - y0) as f32 * cell_size + cell_size / 2.0;
+
+            canvas.text(margin * 0.9, cy, size, "end", &y.to_string());
+        }
+    }
+
+    canvas.elements.push(format!(
+        r#"<g transform="translate({0} {0})">"#,
+        num(margin)
+    ));
+
+    canvas.rectangle(0.0, 0.0, width, height, color(options.theme.background));
+
+    draw_region(
+        &mut canvas,
+        ca,
+        region,
+        (cell_size, cell_size),
+        options.grid,
+        &options.theme,
+    );
+
+    canvas.elements.push("</g>".to_string());
+
+    let (width, height) = (width + margin, height + margin);
+
+    format!(
+        "<svg xmlns=\"http://www.w3.org/2000/svg\" width=\"{0}\" height=\"{1}\" viewBox=\"0 0 {0} {1}\">\n{2}\n</svg>\n",
+        num(width),
+        num(height),
+        canvas.elements.join("\n")
+    )
+}
+
+pub fn export_svg(
+    path: &Path,
+    ca: &CellularAutomation,
+    options: &ImageOptions,
+) -> Result<(), ExportError> {
+    fs::write(path, render_svg(ca, options))?;
+
+    Ok(())
+}
+
+// Числа записываются с точностью до тысячных и без лишних нулей
+fn num(value: f32) -> String {
+    let s = format!("{:.3}", value);
+    let s = s.trim_end_matches('0').trim_end_matches('.');
+
+    if s == "-0" {
+        "0".to_string()
+    } else {
+        s.to_string()
+    }
+}
+
+fn rgb(color: Color) -> String {
+    let [r, g, b, _]: [u8; 4] = color.into();
+
+    format!("#{:02x}{:02x}{:02x}", r, g, b)
+}
+
+fn opacity(attribute: &str, color: Color) -> String {
+    if color.a < 1.0 {
+        format!(r#" {}="{}""#, attribute, num(color.a))
+    } else {
+        String::new()
+    }
+}
@@ -0,0 +1,260 @@
+use super::{check_area, FileError};
+use crate::cellular_automaton::{CellularAutomation, State};
+
+// Двоичный формат (.bdata, а сжатый gzip - .bdata.gz).
+// Заголовок: "KAVN", версия формата (1 байт), такт (u64), левый верхний угол прямоугольника
+// с непустыми клетками (i64, i64), его ширина и высота (u64). Числа записываются в little-endian.
+// Дальше построчно идут серии одинаковых клеток этого прямоугольника:
+// номер состояния (1 байт) и длина серии в кодировке LEB128.
+
+const MAGIC: &[u8; 4] = b"KAVN";
+const VERSION: u8 = 1;
+
+pub fn read(bytes: &[u8]) -> Result<CellularAutomation, FileError> {
+    let mut reader = Reader { bytes, position: 0 };
+
+    if reader.take(MAGIC.len())? != MAGIC {
+        return Err(FileError::Corrupted(
+            "файл не является двоичным сохранением kavn".to_string(),
+        ));
+    }
+
+    let version = reader.take(1)?[0];
+
+    if version != VERSION {
+        return Err(FileError::UnsupportedVersion(version.to_string()));
+    }
+
+    let tact = reader.u64()?;
+    let (x0, y0) = (reader.u64()? as i64, reader.u64()? as i64);
+    let (width, height) = (reader.u64()?, reader.u64()?);
+
+    // Размер проверяется до чтения серий, так как поле выделяется под весь прямоугольник
+    check_area(width, height)?;
+
+    let total = width * height;
+    let coords = |k: u64| {
+        Some((
+            x0.checked_add((k % width) as i64)?,
+            y0.checked_add((k / width) as i64)?,
+        ))
+    };
+
+    let mut cells = vec![];
+    let mut i = 0;
+
+    while i < total {
+        let index = reader.take(1)?[0];
+        let run = reader.leb128()?;
+
+        let state = State::from_index(index)
+            .ok_or_else(|| FileError::Corrupted(format!("неизвестное состояние {}", index)))?;
+
+        if run == 0 || run > total - i {
+            return Err(FileError::Corrupted(format!(
+                "неверная длина серии {}",
+                run
+            )));
+        }
+
+        if state != State::Unexcitable {
+            for k in i..i + run {
+                let coords = coords(k).ok_or_else(|| {
+                    FileError::Corrupted("координаты клетки вне допустимых".to_string())
+                })?;
+
+                cells.push((coords, state));
+            }
+        }
+
+        i += run;
+    }
+
+    Ok(CellularAutomation::from_cells(&cells, tact))
+}
+
+pub fn write(ca: &CellularAutomation) -> Vec<u8> {
+    // Для пустого поля записывается прямоугольник нулевого размера
+    let ((x0, y0), (x1, y1)) = ca.bounds().unwrap_or(((0, 0), (-1, -1)));
+
+    let mut bytes = MAGIC.to_vec();
+
+    bytes.push(VERSION);
+    bytes.extend(ca.tact.to_le_bytes());
+    bytes.extend(x0.to_le_bytes());
+    bytes.extend(y0.to_le_bytes());
+    bytes.extend(((x1 - x0 + 1) as u64).to_le_bytes());
+    bytes.extend(((y1 - y0 + 1) as u64).to_le_bytes());
+
+    let mut run: Option<(u8, u64)> = None;
+
+    for y in y0..=y1 {
+        for x in x0..=x1 {
+            let index = ca.get_cell((x, y)).index();
+
+            run = match run {
+                Some((last, count)) if last == index => Some((last, count + 1)),
+                Some((last, count)) => {
+                    write_run(&mut bytes, last, count);
+                    Some((index, 1))
+                }
+                None => Some((index, 1)),
+            };
+        }
+    }
+
+    if let Some((last, count)) = run {
+        write_run(&mut bytes, last, count);
+    }
+
+    bytes
+}
+
+fn write_run(bytes: &mut Vec<u8>, index: u8, mut count: u64) {
+    bytes.push(index);
+
+    loop {
+        let byte = (count & 0x7f) as u8;
+        count >>= 7;
+
+        if count == 0 {
+            bytes.push(byte);
+            break;
+        }
+
+        bytes.push(byte | 0x80);
+    }
+}
+
+struct Reader<'a> {
+    bytes: &'a [u8],
+    position: usize,
+}
+
+impl<'a> Reader<'a> {
+    fn take(&mut self, n: usize) -> Result<&'a [u8], FileError> {
+        let bytes = self
+            .bytes
+            .get(self.position..self.position + n)
+            .ok_or_else(|| FileError::Corrupted("файл неожиданно закончился".to_string()))?;
+
+        self.position += n;
+
+        Ok(bytes)
+    }
+
+    fn u64(&mut self) -> Result<u64, FileError> {
+        Ok(u64::from_le_bytes(self.take(8)?.try_into().unwrap()))
+    }
+
+    fn leb128(&mut self) -> Result<u64, FileError> {
+        let mut value = 0u64;
+
+        for shift in (0..64).step_by(7) {
+            let byte = self.take(1)?[0];
+
+            value |= ((byte & 0x7f) as u64) << shift;
+
+            if byte & 0x80 == 0 {
+                return Ok(value);
+            }
+        }
+
+        Err(FileError::Corrupted("слишком длинное число".to_string()))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::cellular_automaton::testing::assert_same_field;
+
+    #[test]
+    fn leb128_boundaries() {
+        for value in [0, 1, 127, 128, 16383, 16384, u32::MAX as u64, u64::MAX] {
+            let mut bytes = vec![];
+            write_run(&mut bytes, 7, value);
+
+            let mut reader = Reader {
+                bytes: &bytes,
+                position: 0,
+            };
+
+            assert_eq!(reader.take(1).unwrap(), [7]);
+            assert_eq!(reader.leb128().unwrap(), value, "число {}", value);
+            assert_eq!(reader.position, bytes.len());
+        }
+
+        // Одиннадцатый байт уже не помещается в u64
+        let mut reader = Reader {
+            bytes: &[0x80; 11],
+            position: 0,
+        };
+
+        assert!(matches!(reader.leb128(), Err(FileError::Corrupted(_))));
+    }
+
+    #[test]
+    fn round_trip_long_runs() {
+        let mut ca = CellularAutomation::new();
+
+        // Серии длиной 127, 128 и 16384 клеток на отрицательных координатах
+        for (y, length) in [(-5, 127), (-4, 128), (-3, 16384)] {
+            for x in 0..length {
+                ca.set_cell((x - 100, y), State::Sensitive { value: 3 });
+            }
+        }
+
+        ca.set_cell((-100, 2), State::from_index(28).unwrap());
+        ca.tact = 123;
+
+        assert_same_field(&read(&write(&ca)).unwrap(), &ca);
+    }
+
+    #[test]
+    fn round_trip_empty() {
+        let read_ca = read(&write(&CellularAutomation::new())).unwrap();
+
+        assert_eq!(read_ca.population(), 0);
+    }
+
+    #[test]
+    fn rejects_corrupted() {
+        let mut ca = CellularAutomation::new();
+        ca.set_cell((0, 0), State::Sensitive { value: 1 });
+
+        let bytes = write(&ca);
+
+        assert!(matches!(
+            read(&bytes[..bytes.len() - 1]),
+            Err(FileError::Corrupted(_))
+        ));
+        assert!(matches!(
+            read(b"KAVN\x09"),
+            Err(FileError::UnsupportedVersion(_))
+        ));
+
+        // Огромный прямоугольник с одной клеткой в конце длинной серии
+        let mut huge = b"KAVN\x01".to_vec();
+
+        for value in [0u64, 0, 0, 1 << 40, 1] {
+            huge.extend(value.to_le_bytes());
+        }
+
+        write_run(&mut huge, 0, (1 << 40) - 1);
+        write_run(&mut huge, 1, 1);
+
+        assert!(matches!(read(&huge), Err(FileError::TooLarge { .. })));
+
+        // Клетки за пределами координат i64
+        let mut shifted = b"KAVN\x01".to_vec();
+
+        for value in [0, i64::MAX as u64, 0, 2, 1] {
+            shifted.extend(value.to_le_bytes());
+        }
+
+        write_run(&mut shifted, 1, 2);
+
+        assert!(matches!(read(&shifted), Err(FileError::Corrupted(_))));
+    }
+}
@@ -0,0 +1,140 @@
+use super::{FileError, Metadata};
+use crate::cellular_automaton::CellularAutomation;
+use serde::{Deserialize, Serialize};
+use serde_json::{json, Value};
+
+// Основной формат сохранений (.data) - JSON с номером версии формата.
+// Файлы старых версий при загрузке последовательно приводятся к текущей версии.
+
+pub const VERSION: u64 = 2;
+
+// Миграция с номером i переводит файл версии i в версию i + 1
+const MIGRATIONS: [fn(Value) -> Value; VERSION as usize] = [migrate_v0, migrate_v1];
+
+#[derive(Serialize)]
+struct SaveFileRef<'a> {
+    version: u64,
+    metadata: &'a Metadata,
+    automaton: &'a CellularAutomation,
+}
+
+#[derive(Deserialize)]
+struct SaveFile {
+    metadata: Metadata,
+    automaton: CellularAutomation,
+}
+
+pub fn read(contents: &str) -> Result<(CellularAutomation, Metadata), FileError> {
+    let mut value: Value = serde_json::from_str(contents)?;
+
+    // У самых первых сохранений номера версии не было
+    let version = match value.get("version") {
+        None => 0,
+        Some(version) => version
+            .as_u64()
+            .ok_or_else(|| FileError::UnsupportedVersion(version.to_string()))?,
+    };
+
+    if version > VERSION {
+        return Err(FileError::UnsupportedVersion(version.to_string()));
+    }
+
+    for migration in &MIGRATIONS[version as usize..] {
+        value = migration(value);
+    }
+
+    let file: SaveFile = serde_json::from_value(value)?;
+
+    Ok((file.automaton, file.metadata))
+}
+
+pub fn write(ca: &CellularAutomation, metadata: &Metadata) -> Result<String, FileError> {
+    Ok(serde_json::to_string(&SaveFileRef {
+        version: VERSION,
+        metadata,
+        automaton: ca,
+    })?)
+}
+
+// Версия 0 - поля CellularAutomation (field, tact, center) без какой-либо обёртки
+fn migrate_v0(value: Value) -> Value {
+    json!({
+        "version": 1,
+        "automaton": value,
+    })
+}
+
+// В версии 2 появилось описание поля
+fn migrate_v1(mut value: Value) -> Value {
+    value["version"] = json!(2);
+    value["metadata"] = json!({});
+
+    value
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::cellular_automaton::{Direction, State};
+
+    // Сохранение версии 1: поле уже обёрнуто, но описания ещё нет
+    const V1: &str = r#"{
+        "version": 1,
+        "automaton": {
+            "field": [
+                ["Unexcitable", {"NormalTransmitting": {"dir": "Right", "signal": true}}],
+                [{"Confluence": {"new": false, "old": true}}, "Unexcitable"]
+            ],
+            "tact": 5,
+            "center": [1, 0]
+        }
+    }"#;
+
+    #[test]
+    fn migrates_v1() {
+        let (ca, metadata) = read(V1).unwrap();
+
+        assert_eq!(ca.tact, 5);
+        assert_eq!(
+            *ca.get_cell((0, 0)),
+            State::NormalTransmitting {
+                dir: Direction::Right,
+                signal: true
+            }
+        );
+        assert_eq!(
+            *ca.get_cell((-1, 1)),
+            State::Confluence {
+                new: false,
+                old: true
+            }
+        );
+        assert!(metadata.title.is_empty());
+        assert!(metadata.viewport.is_none());
+
+        // После пересохранения файл читается уже без миграций
+        let text = write(&ca, &metadata).unwrap();
+        let value: Value = serde_json::from_str(&text).unwrap();
+
+        assert_eq!(value["version"], json!(VERSION));
+
+        let (read_ca, _) = read(&text).unwrap();
+
+        assert_eq!(read_ca.fingerprint(), ca.fingerprint());
+    }
+
+    #[test]
+    fn migrates_v0_examples() {
+        let (ca, _) = read(include_str!("../../examples/стрелочки.data")).unwrap();
+
+        assert!(ca.population() > 0);
+    }
+
+    #[test]
+    fn rejects_newer_versions() {
+        assert!(matches!(
+            read(r#"{"version": 3, "metadata": {}, "automaton": {}}"#),
+            Err(FileError::UnsupportedVersion(_))
+        ));
+    }
+}
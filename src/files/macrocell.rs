@@ -0,0 +1,434 @@
+use super::rle::{from_golly_index, golly_index};
+use super::{check_area, FileError};
+use crate::cellular_automaton::{CellularAutomation, Region, State};
+use std::collections::HashMap;
+
+// Формат макроклеток (.mc) из программы Golly с правилом JvN29.
+// Поле хранится квадродеревом: узел уровня 1 - квадрат 2x2 из номеров состояний Golly,
+// узел уровня k - четыре ссылки (СЗ, СВ, ЮЗ, ЮВ) на узлы уровня k - 1, 0 - пустой узел.
+// Узлы нумеруются с 1 в порядке записи, корень записывается последним.
+// Как и в Golly, корень уровня k располагается левым верхним углом в точке (-2^(k-1), -2^(k-1)).
+
+// Больше уровней не поместится в координаты i64
+const MAX_LEVEL: u32 = 62;
+
+enum Node {
+    Leaf([State; 4]),
+    Branch { level: u32, children: [usize; 4] },
+}
+
+impl Node {
+    fn level(&self) -> u32 {
+        match self {
+            Node::Leaf(_) => 1,
+            Node::Branch { level, .. } => *level,
+        }
+    }
+}
+
+pub fn read(contents: &str) -> Result<CellularAutomation, FileError> {
+    let mut tact = 0;
+    let mut rule = None;
+
+    // Нулевой элемент - заглушка для пустого узла, чтобы номера совпадали с индексами
+    let mut nodes: Vec<Option<Node>> = vec![None];
+
+    for (number, line) in contents.lines().enumerate() {
+        let error = |message: String| FileError::Parse {
+            line: number + 1,
+            message,
+        };
+
+        let line = line.trim();
+
+        if line.is_empty() || line.starts_with('[') {
+            continue;
+        }
+
+        if let Some(comment) = line.strip_prefix('#') {
+            if let Some(r) = comment.strip_prefix('R') {
+                rule = Some(r.trim().to_string());
+            } else if let Some(gen) = comment.strip_prefix('G') {
+                tact = gen
+                    .trim()
+                    .parse()
+                    .map_err(|_| error(format!("неверный номер такта \"{}\"", gen.trim())))?;
+            }
+
+            continue;
+        }
+
+        let numbers = line
+            .split_whitespace()
+            .map(|n| n.parse::<usize>())
+            .collect::<Result<Vec<_>, _>>()
+            .map_err(|_| error(format!("неверный узел \"{}\"", line)))?;
+
+        let node = match numbers[..] {
+            [1, nw, ne, sw, se] => {
+                let mut states = [State::Unexcitable; 4];
+
+                for (state, index) in states.iter_mut().zip([nw, ne, sw, se]) {
+                    *state = u8::try_from(index)
+                        .ok()
+                        .and_then(from_golly_index)
+                        .ok_or_else(|| error(format!("неизвестное состояние {}", index)))?;
+                }
+
+                Node::Leaf(states)
+            }
+
+            [level, nw, ne, sw, se] if (2..=MAX_LEVEL as usize).contains(&level) => {
+                let children = [nw, ne, sw, se];
+
+                for &child in &children {
+                    match nodes.get(child) {
+                        Some(None) => {}
+                        Some(Some(node)) if node.level() + 1 == level as u32 => {}
+                        _ => return Err(error(format!("неверная ссылка на узел {}", child))),
+                    }
+                }
+
+                Node::Branch {
+                    level: level as u32,
+                    children,
+                }
+            }
+
+            _ => return Err(error(format!("неверный узел \"{}\"", line))),
+        };
+
+        nodes.push(Some(node));
+    }
+
+    match rule {
+        Some(rule) if rule.eq_ignore_ascii_case("JvN29") => {}
+        rule => return Err(FileError::UnsupportedRule(rule.unwrap_or_default())),
+    }
+
+    let mut cells = vec![];
+
+    if let Some(Some(root)) = nodes.last() {
+        let half = 1i64 << (root.level() - 1);
+
+        // Квадродерево с общими узлами описывает огромное поле несколькими строками,
+        // поэтому размер узора проверяется до раскрытия узлов
+        if let Some(((x0, y0), (x1, y1))) = node_bounds(&nodes)[nodes.len() - 1] {
+            check_area((x1 - x0 + 1) as u64, (y1 - y0 + 1) as u64)?;
+        }
+
+        expand(&nodes, nodes.len() - 1, (-half, -half), &mut cells);
+    }
+
+    Ok(CellularAutomation::from_cells(&cells, tact))
+}
+
+// Прямоугольники с непустыми клетками всех узлов относительно их левых верхних углов.
+// Дочерние узлы записаны раньше родительских, поэтому хватает одного прохода.
+fn node_bounds(nodes: &[Option<Node>]) -> Vec<Option<Region>> {
+    let mut bounds: Vec<Option<Region>> = Vec::with_capacity(nodes.len());
+
+    for node in nodes {
+        let parts: Vec<Region> = match node {
+            None => vec![],
+
+            Some(Node::Leaf(states)) => (0..4)
+                .filter(|&i| states[i] != State::Unexcitable)
+                .map(|i| {
+                    let coords = ((i % 2) as i64, (i / 2) as i64);
+
+                    (coords, coords)
+                })
+                .collect(),
+
+            Some(Node::Branch { level, children }) => {
+                let half = 1i64 << (level - 1);
+
+                children
+                    .iter()
+                    .enumerate()
+                    .filter_map(|(i, &child)| {
+                        let ((x0, y0), (x1, y1)) = bounds[child]?;
+                        let (dx, dy) = ((i % 2) as i64 * half, (i / 2) as i64 * half);
+
+                        Some(((x0 + dx, y0 + dy), (x1 + dx, y1 + dy)))
+                    })
+                    .collect()
+            }
+        };
+
+        bounds.push(parts.into_iter().reduce(
+            |((ax0, ay0), (ax1, ay1)), ((bx0, by0), (bx1, by1))| {
+                ((ax0.min(bx0), ay0.min(by0)), (ax1.max(bx1), ay1.max(by1)))
+            },
+        ));
+    }
+
+    bounds
+}
+
+fn expand(
+    nodes: &[Option<Node>],
+    id: usize,
+    (x, y): (i64, i64),
+    cells: &mut Vec<((i64, i64), State)>,
+) {
+    match &nodes[id] {
+        None => {}
+
+        Some(Node::Leaf(states)) => {
+            for (i, &state) in states.iter().enumerate() {
+                if state != State::Unexcitable {
+                    cells.push(((x + (i % 2) as i64, y + (i / 2) as i64), state));
+                }
+            }
+        }
+
+        Some(Node::Branch { level, children }) => {
+            let half = 1i64 << (level - 1);
+
+            for (i, &child) in children.iter().enumerate() {
+                expand(
+                    nodes,
+                    child,
+                    (x + (i % 2) as i64 * half, y + (i / 2) as i64 * half),
+                    cells,
+                );
+            }
+        }
+    }
+}
+
+pub fn write(ca: &CellularAutomation) -> String {
+    let mut writer = Writer {
+        ca,
+        bounds: ca.bounds(),
+        lines: vec![],
+        leaves: HashMap::new(),
+        branches: HashMap::new(),
+    };
+
+    // Наименьший уровень корня, квадрат которого вмещает все непустые клетки
+    let mut level = 1;
+
+    if let Some(((x0, y0), (x1, y1))) = writer.bounds {
+        let extent = [-x0, -y0, x1 + 1, y1 + 1].into_iter().max().unwrap();
+
+        while (1i64 << (level - 1)) < extent {
+            level += 1;
+        }
+    }
+
+    let half = 1i64 << (level - 1);
+    let root = writer.build((-half, -half), level);
+
+    // Пустое поле записывается единственным пустым листом
+    if root == 0 {
+        writer.lines.push("1 0 0 0 0".to_string());
+    }
+
+    format!(
+        "[M2] (kavn)\n#R JvN29\n#G {}\n{}\n",
+        ca.tact,
+        writer.lines.join("\n")
+    )
+}
+
+struct Writer<'a> {
+    ca: &'a CellularAutomation,
+    bounds: Option<((i64, i64), (i64, i64))>,
+    lines: Vec<String>,
+
+    // Одинаковые узлы записываются один раз
+    leaves: HashMap<[u8; 4], usize>,
+    branches: HashMap<(u32, [usize; 4]), usize>,
+}
+
+impl Writer<'_> {
+    fn build(&mut self, (x, y): (i64, i64), level: u32) -> usize {
+        let size = 1i64 << level;
+
+        // Квадраты вне прямоугольника с непустыми клетками сразу считаются пустыми
+        match self.bounds {
+            Some(((x0, y0), (x1, y1))) if x <= x1 && y <= y1 && x + size > x0 && y + size > y0 => {}
+            _ => return 0,
+        }
+
+        if level == 1 {
+            let mut states = [0; 4];
+
+            for (i, state) in states.iter_mut().enumerate() {
+                let coords = (x + (i % 2) as i64, y + (i / 2) as i64);
+
+                *state = self.get_index(coords);
+            }
+
+            if states == [0; 4] {
+                return 0;
+            }
+
+            let id = self.lines.len() + 1;
+
+            return *self.leaves.entry(states).or_insert_with(|| {
+                self.lines.push(format!(
+                    "1 {} {} {} {}",
+                    states[0], states[1], states[2], states[3]
+                ));
+
+                id
+            });
+        }
+
+        let half = size / 2;
+        let mut children = [0; 4];
+
+        for (i, child) in children.iter_mut().enumerate() {
+            *child = self.build(
+                (x + (i % 2) as i64 * half, y + (i / 2) as i64 * half),
+                level - 1,
+            );
+        }
+
+        if children == [0; 4] {
+            return 0;
+        }
+
+        let id = self.lines.len() + 1;
+
+        *self.branches.entry((level, children)).or_insert_with(|| {
+            self.lines.push(format!(
+                "{} {} {} {} {}",
+                level, children[0], children[1], children[2], children[3]
+            ));
+
+            id
+        })
+    }
+
+    fn get_index(&self, (x, y): (i64, i64)) -> u8 {
+        match self.bounds {
+            Some(((x0, y0), (x1, y1))) if (x0..=x1).contains(&x) && (y0..=y1).contains(&y) => {
+                golly_index(self.ca.get_cell((x, y)))
+            }
+
+            _ => 0,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::cellular_automaton::testing::{assert_same_field, non_empty, state};
+
+    // Узор в том виде, в каком его сохраняет Golly: сигнал на проводе и конфлюентная клетка
+    const GOLLY: &str = "[M2] (golly 4.2)\n\
+                         #R JvN29\n\
+                         1 0 0 17 13\n\
+                         2 0 0 0 1\n\
+                         1 0 0 13 13\n\
+                         2 0 0 3 0\n\
+                         1 0 0 9 0\n\
+                         2 0 5 0 0\n\
+                         3 2 4 0 6\n";
+
+    // Чувствительные состояния с номерами Golly 1-8 в строке y = 0 от x = 0 до x = 7
+    const GOLLY_SENSITIVE: &str = "[M2] (golly 4.2)\n\
+                                   #R JvN29\n\
+                                   1 1 2 0 0\n\
+                                   1 3 4 0 0\n\
+                                   2 1 2 0 0\n\
+                                   1 5 6 0 0\n\
+                                   1 7 8 0 0\n\
+                                   2 4 5 0 0\n\
+                                   3 3 6 0 0\n\
+                                   4 0 0 0 7\n";
+
+    fn node_lines(text: &str) -> Vec<&str> {
+        text.lines()
+            .filter(|line| !line.starts_with(['[', '#']))
+            .collect()
+    }
+
+    #[test]
+    fn reads_golly_file() {
+        let ca = read(GOLLY).unwrap();
+
+        assert_eq!(
+            non_empty(&ca),
+            vec![
+                ((-2, -1), state("T001")),
+                ((-1, -1), state("T000")),
+                ((0, -1), state("T000")),
+                ((1, -1), state("T000")),
+                ((2, 1), state("C00")),
+            ]
+        );
+        assert_eq!(ca.tact, 0);
+    }
+
+    #[test]
+    fn round_trip_matches_golly() {
+        let mut ca = read(GOLLY).unwrap();
+        ca.tact = 40;
+
+        let text = write(&ca);
+
+        // Узлы записываются в том же порядке и с теми же номерами, что и в Golly
+        assert_eq!(node_lines(&text), node_lines(GOLLY));
+
+        assert_same_field(&read(&text).unwrap(), &ca);
+    }
+
+    #[test]
+    fn reads_golly_sensitive_states() {
+        let ca = read(GOLLY_SENSITIVE).unwrap();
+
+        let expected: Vec<_> = ["S", "S0", "S00", "S01", "S000", "S1", "S10", "S11"]
+            .into_iter()
+            .enumerate()
+            .map(|(x, notation)| ((x as i64, 0), state(notation)))
+            .collect();
+
+        assert_eq!(non_empty(&ca), expected);
+        assert_eq!(node_lines(&write(&ca)), node_lines(GOLLY_SENSITIVE));
+    }
+
+    #[test]
+    fn rejects_huge_patterns() {
+        // Две клетки в противоположных углах корня самого большого уровня
+        let mut far = "#R JvN29\n1 13 0 0 0\n1 0 0 0 13\n".to_string();
+
+        for level in 2..MAX_LEVEL {
+            let (nw, se) = (2 * level - 3, 2 * level - 2);
+
+            far.push_str(&format!("{} {} 0 0 0\n{} 0 0 0 {}\n", level, nw, level, se));
+        }
+
+        far.push_str(&format!(
+            "{} {} 0 0 {}\n",
+            MAX_LEVEL,
+            2 * MAX_LEVEL - 3,
+            2 * MAX_LEVEL - 2
+        ));
+
+        assert!(matches!(read(&far), Err(FileError::TooLarge { .. })));
+
+        // Полностью заполненный квадрат из общих узлов
+        let mut filled = "#R JvN29\n1 13 13 13 13\n".to_string();
+
+        for level in 2..=20 {
+            let id = level - 1;
+
+            filled.push_str(&format!("{} {} {} {} {}\n", level, id, id, id, id));
+        }
+
+        assert!(matches!(
+            read(&filled),
+            Err(FileError::TooLarge {
+                width: 0x100000,
+                height: 0x100000
+            })
+        ));
+    }
+}
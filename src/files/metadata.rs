@@ -0,0 +1,46 @@
+use serde::{Deserialize, Serialize};
+
+// Описание сохранённого поля. Полностью сохраняется только в основном формате (.data),
+// в RLE записываются название, автор и описание, остальные форматы описание не хранят.
+#[derive(Serialize, Deserialize, Debug, Clone)]
+#[serde(default)]
+pub struct Metadata {
+    pub title: String,
+    pub author: String,
+    pub description: String,
+    pub rule: String,
+
+    // Положение камеры, масштаб и скорость на момент сохранения
+    pub viewport: Option<Viewport>,
+    pub speed: Option<u64>,
+
+    pub notes: String,
+    pub bookmarks: Vec<Bookmark>,
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone, Copy)]
+pub struct Viewport {
+    pub offset: (f32, f32),
+    pub cell_size: (f32, f32),
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone)]
+pub struct Bookmark {
+    pub name: String,
+    pub coords: (i64, i64),
+}
+
+impl Default for Metadata {
+    fn default() -> Self {
+        Self {
+            title: String::new(),
+            author: String::new(),
+            description: String::new(),
+            rule: "JvN29".to_string(),
+            viewport: None,
+            speed: None,
+            notes: String::new(),
+            bookmarks: vec![],
+        }
+    }
+}
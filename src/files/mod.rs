@@ -0,0 +1,219 @@
+mod binary;
+mod data;
+mod macrocell;
+mod metadata;
+mod rle;
+mod sparse;
+mod text;
+
+pub use metadata::*;
+
+use crate::cellular_automaton::CellularAutomation;
+use flate2::read::GzDecoder;
+use flate2::write::GzEncoder;
+use flate2::Compression;
+use std::fmt::{Display, Formatter};
+use std::fs;
+use std::io::{self, Read, Write};
+use std::path::Path;
+
+#[derive(Debug)]
+pub enum FileError {
+    Io(std::io::Error),
+    Json(serde_json::Error),
+    Parse { line: usize, message: String },
+    Corrupted(String),
+    UnsupportedRule(String),
+    UnsupportedVersion(String),
+    TooLarge { width: u64, height: u64 },
+}
+
+impl Display for FileError {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        match self {
+            FileError::Io(err) => write!(f, "Ошибка чтения/записи файла: {}", err),
+            FileError::Json(err) => write!(f, "Ошибка в JSON: {}", err),
+            FileError::Parse { line, message } => {
+                write!(f, "Ошибка в строке {}: {}", line, message)
+            }
+            FileError::Corrupted(message) => write!(f, "Файл повреждён: {}", message),
+            FileError::UnsupportedRule(rule) => {
+                write!(f, "Правило \"{}\" не поддерживается, ожидалось JvN29", rule)
+            }
+            FileError::UnsupportedVersion(version) => write!(
+                f,
+                "Версия формата {} не поддерживается. \
+                 Возможно, файл сохранён более новой версией программы",
+                version
+            ),
+            FileError::TooLarge { width, height } => write!(
+                f,
+                "Узор размером {}x{} клеток слишком велик для загрузки",
+                width, height
+            ),
+        }
+    }
+}
+
+impl From<std::io::Error> for FileError {
+    fn from(err: std::io::Error) -> Self {
+        FileError::Io(err)
+    }
+}
+
+impl From<serde_json::Error> for FileError {
+    fn from(err: serde_json::Error) -> Self {
+        FileError::Json(err)
+    }
+}
+
+// Поле хранится целиком, поэтому узоры с прямоугольником больше этой площади не загружаются
+const MAX_AREA: u64 = 1 << 26;
+
+// Проверка размера узора до того, как под него выделяется поле
+fn check_area(width: u64, height: u64) -> Result<(), FileError> {
+    match width.checked_mul(height) {
+        Some(area) if area <= MAX_AREA => Ok(()),
+        _ => Err(FileError::TooLarge { width, height }),
+    }
+}
+
+// Фильтры для диалогов открытия и сохранения файлов
+pub const FILTERS: [(&str, &[&str]); 7] = [
+    ("data", &["data"]),
+    ("Разреженный JSON", &["sdata"]),
+    ("Двоичный", &["bdata"]),
+    ("Двоичный сжатый", &["gz"]),
+    ("Текстовый", &["tdata"]),
+    ("Golly RLE", &["rle"]),
+    ("Golly macrocell", &["mc"]),
+];
+
+// Форматы файлов определяются по расширению, всё неизвестное считается основным форматом (.data)
+enum Format {
+    Json,
+    Sparse,
+    Binary { compressed: bool },
+    Text,
+    Rle,
+    Macrocell,
+}
+
+impl Format {
+    fn of(path: &Path) -> Self {
+        let extension = path
+            .extension()
+            .and_then(|e| e.to_str())
+            .map(|e| e.to_lowercase());
+
+        match extension.as_deref() {
+            Some("sdata") => Format::Sparse,
+            Some("bdata") => Format::Binary { compressed: false },
+            Some("gz") => match path.file_stem().map(|stem| Format::of(Path::new(stem))) {
+                Some(Format::Binary { .. }) => Format::Binary { compressed: true },
+                _ => Format::Json,
+            },
+            Some("tdata") => Format::Text,
+            Some("rle") => Format::Rle,
+            Some("mc") => Format::Macrocell,
+            _ => Format::Json,
+        }
+    }
+}
+
+// Загруженное из файла поле вместе с его описанием
+pub struct Document {
+    pub automaton: CellularAutomation,
+    pub metadata: Metadata,
+}
+
+pub fn load(path: &Path) -> Result<Document, FileError> {
+    let bytes = fs::read(path)?;
+
+    let (automaton, metadata) = match Format::of(path) {
+        Format::Binary { compressed: false } => (binary::read(&bytes)?, Metadata::default()),
+
+        Format::Binary { compressed: true } => {
+            let mut decoded = vec![];
+            GzDecoder::new(&bytes[..]).read_to_end(&mut decoded)?;
+
+            (binary::read(&decoded)?, Metadata::default())
+        }
+
+        format => {
+            let contents = String::from_utf8(bytes)
+                .map_err(|err| io::Error::new(io::ErrorKind::InvalidData, err))?;
+
+            match format {
+                Format::Sparse => (sparse::read(&contents)?, Metadata::default()),
+                Format::Text => (text::read(&contents)?, Metadata::default()),
+                Format::Rle => rle::read(&contents)?,
+                Format::Macrocell => (macrocell::read(&contents)?, Metadata::default()),
+                _ => data::read(&contents)?,
+            }
+        }
+    };
+
+    Ok(Document {
+        automaton,
+        metadata,
+    })
+}
+
+pub fn save(path: &Path, ca: &CellularAutomation, metadata: &Metadata) -> Result<(), FileError> {
+    let contents = match Format::of(path) {
+        Format::Json => data::write(ca, metadata)?.into_bytes(),
+        Format::Sparse => sparse::write(ca)?.into_bytes(),
+        Format::Binary { compressed: false } => binary::write(ca),
+
+        Format::Binary { compressed: true } => {
+            let mut encoder = GzEncoder::new(vec![], Compression::default());
+            encoder.write_all(&binary::write(ca))?;
+
+            encoder.finish()?
+        }
+
+        Format::Text => text::write(ca).into_bytes(),
+        Format::Rle => rle::write(ca, metadata).into_bytes(),
+        Format::Macrocell => macrocell::write(ca).into_bytes(),
+    };
+
+    fs::write(path, contents)?;
+
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::cellular_automaton::testing::assert_same_field;
+    use crate::cellular_automaton::State;
+
+    #[test]
+    fn compressed_binary_round_trip() {
+        let mut ca = CellularAutomation::new();
+
+        for x in -300..300 {
+            ca.set_cell(
+                (x, -7),
+                State::Confluence {
+                    new: true,
+                    old: x % 2 == 0,
+                },
+            );
+        }
+
+        let path = std::env::temp_dir().join(format!("kavn-test-{}.bdata.gz", std::process::id()));
+
+        save(&path, &ca, &Metadata::default()).unwrap();
+
+        let bytes = fs::read(&path).unwrap();
+        let document = load(&path);
+
+        fs::remove_file(&path).unwrap();
+
+        // Файл действительно сжат gzip
+        assert_eq!(bytes[..2], [0x1f, 0x8b]);
+        assert_same_field(&document.unwrap().automaton, &ca);
+    }
+}
@@ -0,0 +1,444 @@
+use super::{check_area, FileError, Metadata};
+use crate::cellular_automaton::{CellularAutomation, State};
+
+// Формат RLE из программы Golly с правилом JvN29.
+// Номера состояний Golly (см. golly_index): 0 - ".", 1-24 - "A"-"X", 25-28 - "pA"-"pD".
+// Положение узора и такт хранятся в строке "#CXRLE Pos=x,y Gen=tact",
+// название, автор и описание - в строках "#N", "#O" и "#C".
+
+const MAX_LINE_LENGTH: usize = 70;
+
+// Golly нумерует чувствительные состояния (1-8) в порядке обхода дерева их кодов,
+// а не по возрастанию кода, как State::index. Остальные номера совпадают.
+const GOLLY_SENSITIVE: [&str; 8] = ["S", "S0", "S00", "S01", "S000", "S1", "S10", "S11"];
+
+// Номер состояния в файлах Golly (RLE и .mc)
+pub(super) fn golly_index(state: &State) -> u8 {
+    let notation = state.notation();
+
+    match GOLLY_SENSITIVE.iter().position(|s| *s == notation) {
+        Some(position) => position as u8 + 1,
+        None => state.index(),
+    }
+}
+
+pub(super) fn from_golly_index(index: u8) -> Option<State> {
+    match index {
+        1..=8 => State::from_notation(GOLLY_SENSITIVE[index as usize - 1]),
+        index => State::from_index(index),
+    }
+}
+
+pub fn read(contents: &str) -> Result<(CellularAutomation, Metadata), FileError> {
+    let mut metadata = Metadata::default();
+    let mut description = vec![];
+
+    let mut position = None;
+    let mut tact = 0;
+    let mut size = None;
+
+    let mut cells = vec![];
+    let (mut x, mut y) = (0i64, 0i64);
+
+    // Правая граница непустых клеток, чтобы проверять размер узора до добавления клеток
+    let mut right = 0i64;
+
+    let overflow =
+        || FileError::Corrupted("слишком большие координаты или число повторений".to_string());
+
+    // Число повторений и префикс многосимвольного состояния могут переноситься на следующую строку
+    let mut count: Option<i64> = None;
+    let mut prefix: Option<char> = None;
+
+    'lines: for (number, line) in contents.lines().enumerate() {
+        let error = |message: String| FileError::Parse {
+            line: number + 1,
+            message,
+        };
+
+        let line = line.trim();
+
+        if line.is_empty() {
+            continue;
+        }
+
+        if let Some(comment) = line.strip_prefix('#') {
+            if let Some(cxrle) = comment.strip_prefix("CXRLE") {
+                for item in cxrle.split_whitespace() {
+                    match item.split_once('=') {
+                        Some(("Pos", pos)) => {
+                            position =
+                                Some(parse_pair(pos).ok_or_else(|| {
+                                    error(format!("неверная позиция \"{}\"", pos))
+                                })?);
+                        }
+
+                        Some(("Gen", gen)) => {
+                            tact = gen
+                                .parse()
+                                .map_err(|_| error(format!("неверный номер такта \"{}\"", gen)))?;
+                        }
+
+                        _ => {}
+                    }
+                }
+            } else if let Some(title) = comment.strip_prefix('N') {
+                metadata.title = title.trim().to_string();
+            } else if let Some(author) = comment.strip_prefix('O') {
+                metadata.author = author.trim().to_string();
+            } else if let Some(line) = comment.strip_prefix(['C', 'c']) {
+                description.push(line.trim());
+            }
+
+            continue;
+        }
+
+        if size.is_none() {
+            size = Some(parse_header(line, number + 1)?);
+            continue;
+        }
+
+        for c in line.chars() {
+            match c {
+                '0'..='9' => {
+                    let digit = c.to_digit(10).unwrap() as i64;
+
+                    count = Some(
+                        count
+                            .unwrap_or(0)
+                            .checked_mul(10)
+                            .and_then(|count| count.checked_add(digit))
+                            .ok_or_else(overflow)?,
+                    );
+                }
+
+                '$' => {
+                    y = y
+                        .checked_add(count.take().unwrap_or(1))
+                        .ok_or_else(overflow)?;
+                    x = 0;
+                }
+
+                '!' => break 'lines,
+
+                'p'..='y' => prefix = Some(c),
+
+                '.' | 'b' | 'o' | 'A'..='X' => {
+                    let index = match c {
+                        '.' | 'b' => 0,
+                        'o' => 1,
+                        c => {
+                            let high = prefix.take().map_or(0, |p| p as u32 - 'p' as u32 + 1);
+
+                            high * 24 + (c as u32 - 'A' as u32) + 1
+                        }
+                    };
+
+                    let state = u8::try_from(index)
+                        .ok()
+                        .and_then(from_golly_index)
+                        .ok_or_else(|| error(format!("неизвестное состояние {}", index)))?;
+
+                    let run = count.take().unwrap_or(1);
+                    let end = x.checked_add(run).ok_or_else(overflow)?;
+
+                    if state != State::Unexcitable {
+                        right = right.max(end);
+                        check_area(right as u64, y as u64 + 1)?;
+
+                        cells.extend((x..end).map(|x| ((x, y), state)));
+                    }
+
+                    x = end;
+                }
+
+                c if c.is_whitespace() => {}
+
+                c => return Err(error(format!("неожиданный символ '{}'", c))),
+            }
+        }
+    }
+
+    let (width, height) = size.ok_or(FileError::Parse {
+        line: contents.lines().count(),
+        message: "отсутствует заголовок \"x = ..., y = ...\"".to_string(),
+    })?;
+
+    // Без явной позиции Golly располагает узор по центру
+    let (px, py) = position.unwrap_or((-(width / 2), -(height / 2)));
+
+    for ((x, y), _) in cells.iter_mut() {
+        *x = x.checked_add(px).ok_or_else(overflow)?;
+        *y = y.checked_add(py).ok_or_else(overflow)?;
+    }
+
+    metadata.description = description.join("\n");
+
+    Ok((CellularAutomation::from_cells(&cells, tact), metadata))
+}
+
+pub fn write(ca: &CellularAutomation, metadata: &Metadata) -> String {
+    let ((x0, y0), (x1, y1)) = ca.bounds().unwrap_or(((0, 0), (0, 0)));
+
+    let mut items = vec![];
+    let mut last_row = None;
+
+    for y in y0..=y1 {
+        let mut runs: Vec<(u8, i64)> = vec![];
+
+        for x in x0..=x1 {
+            let index = golly_index(ca.get_cell((x, y)));
+
+            match runs.last_mut() {
+                Some((last, count)) if *last == index => *count += 1,
+                _ => runs.push((index, 1)),
+            }
+        }
+
+        // Пустые клетки в конце строки не записываются
+        if let Some((0, _)) = runs.last() {
+            runs.pop();
+        }
+
+        if runs.is_empty() {
+            continue;
+        }
+
+        if let Some(last_row) = last_row {
+            items.push(run_item(y - last_row, "$"));
+        }
+
+        last_row = Some(y);
+
+        for (index, count) in runs {
+            items.push(run_item(count, &state_code(index)));
+        }
+    }
+
+    items.push("!".to_string());
+
+    let mut result = format!("#CXRLE Pos={},{} Gen={}\n", x0, y0, ca.tact);
+
+    if !metadata.title.is_empty() {
+        result.push_str(&format!("#N {}\n", metadata.title));
+    }
+
+    if !metadata.author.is_empty() {
+        result.push_str(&format!("#O {}\n", metadata.author));
+    }
+
+    for line in metadata.description.lines() {
+        result.push_str(&format!("#C {}\n", line));
+    }
+
+    result.push_str(&format!(
+        "x = {}, y = {}, rule = JvN29\n",
+        x1 - x0 + 1,
+        y1 - y0 + 1
+    ));
+
+    let mut line = String::new();
+
+    for item in items {
+        if line.len() + item.len() > MAX_LINE_LENGTH {
+            result.push_str(&line);
+            result.push('\n');
+            line.clear();
+        }
+
+        line.push_str(&item);
+    }
+
+    result.push_str(&line);
+    result.push('\n');
+
+    result
+}
+
+fn state_code(index: u8) -> String {
+    match index {
+        0 => ".".to_string(),
+        1..=24 => ((b'A' + index - 1) as char).to_string(),
+        _ => {
+            let (high, low) = ((index - 25) / 24, (index - 25) % 24);
+
+            format!("{}{}", (b'p' + high) as char, (b'A' + low) as char)
+        }
+    }
+}
+
+fn run_item(count: i64, code: &str) -> String {
+    if count == 1 {
+        code.to_string()
+    } else {
+        format!("{}{}", count, code)
+    }
+}
+
+fn parse_pair(s: &str) -> Option<(i64, i64)> {
+    let (x, y) = s.split_once(',')?;
+
+    Some((x.trim().parse().ok()?, y.trim().parse().ok()?))
+}
+
+// Разбор строки вида "x = 5, y = 3, rule = JvN29"
+fn parse_header(line: &str, number: usize) -> Result<(i64, i64), FileError> {
+    let error = |message: String| FileError::Parse {
+        line: number,
+        message,
+    };
+
+    let (mut width, mut height, mut rule) = (None, None, None);
+
+    for item in line.split(',') {
+        let (key, value) = item
+            .split_once('=')
+            .ok_or_else(|| error(format!("неверный заголовок \"{}\"", line)))?;
+
+        let (key, value) = (key.trim(), value.trim());
+
+        match key {
+            "x" => width = value.parse().ok(),
+            "y" => height = value.parse().ok(),
+            "rule" => rule = Some(value.to_string()),
+            _ => {}
+        }
+    }
+
+    match rule {
+        Some(rule) if rule.eq_ignore_ascii_case("JvN29") => {}
+        rule => return Err(FileError::UnsupportedRule(rule.unwrap_or_default())),
+    }
+
+    match (width, height) {
+        (Some(width), Some(height)) => Ok((width, height)),
+        _ => Err(error(format!("неверный размер узора в \"{}\"", line))),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::cellular_automaton::testing::{all_states, assert_same_field, non_empty, state};
+
+    // Узор в том виде, в каком его сохраняет Golly: чувствительные состояния с номерами 1-8 (A-H),
+    // под ними конфлюентная клетка и провод
+    const GOLLY: &str = "#CXRLE Pos=0,-1\n\
+                         x = 8, y = 2, rule = JvN29\n\
+                         ABCDEFGH$I3M!\n";
+
+    #[test]
+    fn round_trip_all_states() {
+        // Все состояния, включая многосимвольные "pA"-"pD",
+        // и серии одинаковых клеток с пустыми строками перед ними
+        let mut ca = all_states((-10, -3));
+
+        for x in 0..12 {
+            ca.set_cell((x, 4), State::from_index(28).unwrap());
+            ca.set_cell((x, 5), State::from_index(13).unwrap());
+        }
+
+        ca.tact = 17;
+
+        let metadata = Metadata {
+            title: "Проверка".to_string(),
+            author: "kavn".to_string(),
+            description: "первая строка\nвторая строка".to_string(),
+            ..Metadata::default()
+        };
+
+        let text = write(&ca, &metadata);
+
+        assert!(text.replace('\n', "").contains("3$10.12pD$10.12M!"));
+        assert!(text.lines().all(|line| line.len() <= MAX_LINE_LENGTH));
+
+        let (read_ca, read_metadata) = read(&text).unwrap();
+
+        assert_same_field(&read_ca, &ca);
+        assert_eq!(read_metadata.title, metadata.title);
+        assert_eq!(read_metadata.author, metadata.author);
+        assert_eq!(read_metadata.description, metadata.description);
+    }
+
+    #[test]
+    fn reads_golly_runs() {
+        // Число повторений и префикс перенесены на следующую строку, после "!" всё игнорируется
+        let text = "#N пример\n\
+                    x = 5, y = 3, rule = JvN29\n\
+                    2AbpA$\n\
+                    2$3\n\
+                    pB!\n\
+                    неверный текст";
+
+        let (ca, metadata) = read(text).unwrap();
+
+        // Без #CXRLE узор располагается по центру
+        assert_eq!(
+            non_empty(&ca),
+            vec![
+                ((-2, -1), state("S")),
+                ((-1, -1), state("S")),
+                ((1, -1), state("T101")),
+                ((-2, 2), state("T111")),
+                ((-1, 2), state("T111")),
+                ((0, 2), state("T111")),
+            ]
+        );
+        assert_eq!(metadata.title, "пример");
+    }
+
+    #[test]
+    fn reads_golly_sensitive_states() {
+        let (ca, _) = read(GOLLY).unwrap();
+
+        let mut expected: Vec<_> = ["S", "S0", "S00", "S01", "S000", "S1", "S10", "S11"]
+            .into_iter()
+            .enumerate()
+            .map(|(x, notation)| ((x as i64, -1), state(notation)))
+            .collect();
+
+        expected.extend([
+            ((0, 0), state("C00")),
+            ((1, 0), state("T000")),
+            ((2, 0), state("T000")),
+            ((3, 0), state("T000")),
+        ]);
+
+        assert_eq!(non_empty(&ca), expected);
+
+        // При записи номера состояний те же, что и у Golly
+        assert!(write(&ca, &Metadata::default())
+            .ends_with("x = 8, y = 2, rule = JvN29\nABCDEFGH$I3M!\n"));
+    }
+
+    #[test]
+    fn rejects_bad_input() {
+        assert!(matches!(
+            read("x = 1, y = 1, rule = B3/S23\no!"),
+            Err(FileError::UnsupportedRule(_))
+        ));
+        assert!(matches!(
+            read("x = 1, y = 1, rule = JvN29\nqX!"),
+            Err(FileError::Parse { line: 2, .. })
+        ));
+        assert!(matches!(read("A!"), Err(FileError::Parse { .. })));
+
+        // Переполнение числа повторений и слишком большие узоры
+        assert!(matches!(
+            read("x = 1, y = 1, rule = JvN29\n99999999999999999999A!"),
+            Err(FileError::Corrupted(_))
+        ));
+        assert!(matches!(
+            read("x = 1, y = 1, rule = JvN29\n99999999999A!"),
+            Err(FileError::TooLarge { .. })
+        ));
+        assert!(matches!(
+            read("x = 1, y = 1, rule = JvN29\n99999$99999A!"),
+            Err(FileError::TooLarge { .. })
+        ));
+        assert!(matches!(
+            read("#CXRLE Pos=9223372036854775807,0\nx = 2, y = 1, rule = JvN29\n2A!"),
+            Err(FileError::Corrupted(_))
+        ));
+    }
+}
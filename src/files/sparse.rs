@@ -0,0 +1,65 @@
+use super::FileError;
+use crate::cellular_automaton::{CellularAutomation, State};
+use serde::{Deserialize, Serialize};
+
+// Разреженный формат (.sdata) - JSON только с непустыми клетками.
+// Каждая клетка записывается как [x, y, номер состояния].
+
+#[derive(Serialize, Deserialize)]
+struct SparseFile {
+    tact: u64,
+    cells: Vec<(i64, i64, u8)>,
+}
+
+pub fn read(contents: &str) -> Result<CellularAutomation, FileError> {
+    let file: SparseFile = serde_json::from_str(contents)?;
+
+    let cells = file
+        .cells
+        .into_iter()
+        .map(|(x, y, index)| match State::from_index(index) {
+            Some(state) => Ok(((x, y), state)),
+            None => Err(FileError::Corrupted(format!(
+                "неизвестное состояние {} в клетке ({}, {})",
+                index, x, y
+            ))),
+        })
+        .collect::<Result<Vec<_>, _>>()?;
+
+    Ok(CellularAutomation::from_cells(&cells, file.tact))
+}
+
+pub fn write(ca: &CellularAutomation) -> Result<String, FileError> {
+    let cells = ca
+        .cells()
+        .filter(|(_, state)| **state != State::Unexcitable)
+        .map(|((x, y), state)| (x, y, state.index()))
+        .collect();
+
+    Ok(serde_json::to_string(&SparseFile {
+        tact: ca.tact,
+        cells,
+    })?)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::cellular_automaton::testing::{all_states, assert_same_field};
+
+    #[test]
+    fn round_trip() {
+        let mut ca = all_states((-30, 12));
+        ca.tact = 9;
+
+        assert_same_field(&read(&write(&ca).unwrap()).unwrap(), &ca);
+    }
+
+    #[test]
+    fn rejects_unknown_state() {
+        assert!(matches!(
+            read(r#"{"tact": 0, "cells": [[1, 2, 29]]}"#),
+            Err(FileError::Corrupted(_))
+        ));
+    }
+}
@@ -0,0 +1,140 @@
+use super::FileError;
+use crate::cellular_automaton::{CellularAutomation, State};
+
+// Текстовый формат (.tdata) для просмотра и сравнения в системах контроля версий.
+// После заголовка с координатами левого верхнего угла и тактом идёт прямоугольник
+// с непустыми клетками, каждая клетка записана обозначением из README (U, S01, C10, T131...):
+//
+// # kavn
+// origin -1 0
+// tact 12
+// T000 C00  T130
+// U    S01  U
+
+// Самые длинные обозначения (S000, T131) занимают 4 символа
+const CELL_WIDTH: usize = 4;
+
+pub fn read(contents: &str) -> Result<CellularAutomation, FileError> {
+    let mut origin = None;
+    let mut tact = 0;
+
+    let mut cells = vec![];
+    let mut y = 0;
+
+    for (number, line) in contents.lines().enumerate() {
+        let error = |message: String| FileError::Parse {
+            line: number + 1,
+            message,
+        };
+
+        let mut words = line.split_whitespace().peekable();
+
+        match words.peek() {
+            None => continue,
+            Some(word) if word.starts_with('#') => continue,
+
+            Some(&"origin") => {
+                let coords = words
+                    .skip(1)
+                    .map(|c| c.parse::<i64>())
+                    .collect::<Result<Vec<_>, _>>();
+
+                match coords.as_deref() {
+                    Ok(&[x, y]) => origin = Some((x, y)),
+                    _ => return Err(error(format!("неверные координаты \"{}\"", line))),
+                }
+            }
+
+            Some(&"tact") => {
+                tact = words
+                    .nth(1)
+                    .and_then(|t| t.parse().ok())
+                    .ok_or_else(|| error(format!("неверный номер такта \"{}\"", line)))?;
+            }
+
+            Some(_) => {
+                let (x0, y0) =
+                    origin.ok_or_else(|| error("клетки указаны раньше \"origin\"".to_string()))?;
+
+                for (x, word) in words.enumerate() {
+                    let state = State::from_notation(word)
+                        .ok_or_else(|| error(format!("неизвестное состояние \"{}\"", word)))?;
+
+                    if state != State::Unexcitable {
+                        cells.push(((x0 + x as i64, y0 + y), state));
+                    }
+                }
+
+                y += 1;
+            }
+        }
+    }
+
+    Ok(CellularAutomation::from_cells(&cells, tact))
+}
+
+pub fn write(ca: &CellularAutomation) -> String {
+    let bounds = ca.bounds();
+    let (x0, y0) = bounds.map_or((0, 0), |(min, _)| min);
+
+    let mut result = format!("# kavn\norigin {} {}\ntact {}\n", x0, y0, ca.tact);
+
+    if let Some(((x0, y0), (x1, y1))) = bounds {
+        for y in y0..=y1 {
+            let row = (x0..=x1)
+                .map(|x| {
+                    format!(
+                        "{:width$}",
+                        ca.get_cell((x, y)).notation(),
+                        width = CELL_WIDTH
+                    )
+                })
+                .collect::<Vec<_>>()
+                .join(" ");
+
+            result.push_str(row.trim_end());
+            result.push('\n');
+        }
+    }
+
+    result
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::cellular_automaton::testing::{all_states, assert_same_field};
+
+    #[test]
+    fn round_trip_all_states() {
+        // Все состояния, включая невозбудимое внутри прямоугольника, на отрицательных координатах
+        let mut ca = all_states((-40, -25));
+        ca.tact = 31;
+
+        let text = write(&ca);
+
+        assert!(text.contains("origin -40 -25\n"));
+        assert_same_field(&read(&text).unwrap(), &ca);
+    }
+
+    #[test]
+    fn reads_documented_example() {
+        let ca = read("# kavn\norigin -1 0\ntact 12\nT000 C00  T130\nU    S01  U\n").unwrap();
+
+        assert_eq!(ca.bounds(), Some(((-1, 0), (1, 1))));
+        assert_eq!(ca.population(), 4);
+        assert_eq!(ca.tact, 12);
+    }
+
+    #[test]
+    fn rejects_bad_input() {
+        assert!(matches!(
+            read("U S01\n"),
+            Err(FileError::Parse { line: 1, .. })
+        ));
+        assert!(matches!(
+            read("origin 0 0\nU X\n"),
+            Err(FileError::Parse { line: 2, .. })
+        ));
+    }
+}
@@ -0,0 +1,15 @@
+// Ядро программы без окна: клеточный автомат и форматы сохранений.
+// Его используют само приложение и привязки для других языков.
+
+// Правила перехода между состояниями оставлены в исходном виде
+#[allow(clippy::needless_bool, clippy::derivable_impls)]
+pub mod cellular_automaton;
+pub mod files;
+
+#[cfg(feature = "python")]
+mod python;
+
+// Требования к указателям описаны один раз в начале модуля, а не у каждой функции
+#[cfg(feature = "capi")]
+#[allow(clippy::missing_safety_doc)]
+mod capi;
@@ -1,26 +1,62 @@
 use macroquad::prelude::*;
 use std::rc::Rc;
+use std::sync::mpsc::Receiver;
 use std::sync::RwLock;
 use std::thread::sleep;
 use std::time::{Duration, Instant};
 
-mod cellular_automaton;
+mod cli;
 mod draw;
+mod export;
+mod rpc;
+mod script;
+mod settings;
+mod tui;
 mod ui;
 
+use kavn::{cellular_automaton, files};
+
 use crate::cellular_automaton::CellularAutomation;
+use crate::files::Document;
 use crate::ui::UI;
 
-#[macroquad::main("Клеточный автомат фон Неймана (наверное работает)")]
-async fn main() {
+fn main() {
+    let args: Vec<String> = std::env::args().skip(1).collect();
+
+    // С аргументами программа работает как консольная утилита и окно не открывает
+    if !args.is_empty() {
+        std::process::exit(cli::main(&args));
+    }
+
+    open_window(None, None);
+}
+
+// Открытие окна с полем из документа (или пустым полем). Если передан источник
+// запросов JSON-RPC, окно выполняет и их.
+pub fn open_window(document: Option<Document>, rpc: Option<Receiver<rpc::Request>>) {
+    macroquad::Window::new(
+        "Клеточный автомат фон Неймана (наверное работает)",
+        gui(document, rpc),
+    );
+}
+
+async fn gui(document: Option<Document>, rpc: Option<Receiver<rpc::Request>>) {
     let ca = CellularAutomation::new();
 
     let mut ui = UI::new(Rc::new(RwLock::new(ca)));
 
+    if let Some(document) = document {
+        ui.open_document(document);
+    }
+
+    if let Some(receiver) = rpc {
+        ui.listen(receiver);
+    }
+
     loop {
         let now = Instant::now();
 
-        clear_background(WHITE);
+        clear_background(ui.background());
 
         egui_macroquad::ui(|ctx| {
             ui.draw(ctx);
@@ -0,0 +1,302 @@
+use crate::cellular_automaton::{CellularAutomation, Direction, State};
+use crate::files::{self, FileError, Metadata};
+use numpy::ndarray::Array2;
+use numpy::{IntoPyArray, PyArray2, PyReadonlyArray2};
+use pyo3::exceptions::{PyIOError, PyValueError};
+use pyo3::prelude::*;
+use std::path::PathBuf;
+
+// Модуль kavn для Python. Состояния клеток в массивах numpy хранятся номерами
+// от 0 до 28 (State.index), строка массива соответствует y, столбец - x.
+
+fn file_error(err: FileError) -> PyErr {
+    match err {
+        FileError::Io(err) => PyIOError::new_err(err.to_string()),
+        err => PyValueError::new_err(err.to_string()),
+    }
+}
+
+fn state_from_index(index: u8) -> PyResult<State> {
+    State::from_index(index)
+        .ok_or_else(|| PyValueError::new_err(format!("неизвестный номер состояния {}", index)))
+}
+
+#[pyclass(name = "Direction", eq, eq_int, frozen)]
+#[derive(Clone, Copy, PartialEq)]
+enum PyDirection {
+    Up,
+    Right,
+    Down,
+    Left,
+}
+
+impl From<Direction> for PyDirection {
+    fn from(dir: Direction) -> Self {
+        match dir {
+            Direction::Up => PyDirection::Up,
+            Direction::Right => PyDirection::Right,
+            Direction::Down => PyDirection::Down,
+            Direction::Left => PyDirection::Left,
+        }
+    }
+}
+
+impl From<PyDirection> for Direction {
+    fn from(dir: PyDirection) -> Self {
+        match dir {
+            PyDirection::Up => Direction::Up,
+            PyDirection::Right => Direction::Right,
+            PyDirection::Down => Direction::Down,
+            PyDirection::Left => Direction::Left,
+        }
+    }
+}
+
+#[pyclass(name = "State", frozen)]
+#[derive(Clone, Copy)]
+struct PyState(State);
+
+#[pymethods]
+impl PyState {
+    // State("T000") - по обозначению из README
+    #[new]
+    fn new(notation: &str) -> PyResult<Self> {
+        State::from_notation(notation)
+            .map(PyState)
+            .ok_or_else(|| PyValueError::new_err(format!("неизвестное состояние \"{}\"", notation)))
+    }
+
+    #[staticmethod]
+    fn from_index(index: u8) -> PyResult<Self> {
+        state_from_index(index).map(PyState)
+    }
+
+    #[staticmethod]
+    fn unexcitable() -> Self {
+        PyState(State::Unexcitable)
+    }
+
+    #[staticmethod]
+    #[pyo3(signature = (direction, signal = false))]
+    fn normal(direction: PyDirection, signal: bool) -> Self {
+        PyState(State::NormalTransmitting {
+            dir: direction.into(),
+            signal,
+        })
+    }
+
+    #[staticmethod]
+    #[pyo3(signature = (direction, signal = false))]
+    fn special(direction: PyDirection, signal: bool) -> Self {
+        PyState(State::SpecialTransmitting {
+            dir: direction.into(),
+            signal,
+        })
+    }
+
+    #[staticmethod]
+    #[pyo3(signature = (new = false, old = false))]
+    fn confluence(new: bool, old: bool) -> Self {
+        PyState(State::Confluence { new, old })
+    }
+
+    #[getter]
+    fn index(&self) -> u8 {
+        self.0.index()
+    }
+
+    #[getter]
+    fn notation(&self) -> String {
+        self.0.notation()
+    }
+
+    // Направление передающего состояния, у остальных - None
+    #[getter]
+    fn direction(&self) -> Option<PyDirection> {
+        match self.0 {
+            State::NormalTransmitting { dir, .. } | State::SpecialTransmitting { dir, .. } => {
+                Some(dir.into())
+            }
+            _ => None,
+        }
+    }
+
+    fn __repr__(&self) -> String {
+        format!("State('{}')", self.0.notation())
+    }
+
+    fn __eq__(&self, other: &Self) -> bool {
+        self.0 == other.0
+    }
+
+    fn __hash__(&self) -> u64 {
+        self.0.index() as u64
+    }
+}
+
+// Состояние можно передать объектом State, номером или обозначением
+#[derive(FromPyObject)]
+enum StateArg {
+    State(PyState),
+    Index(u8),
+    Notation(String),
+}
+
+impl StateArg {
+    fn state(self) -> PyResult<State> {
+        match self {
+            StateArg::State(state) => Ok(state.0),
+            StateArg::Index(index) => state_from_index(index),
+            StateArg::Notation(notation) => PyState::new(&notation).map(|state| state.0),
+        }
+    }
+}
+
+#[pyclass(name = "CellularAutomation")]
+#[derive(Clone)]
+struct PyCellularAutomation {
+    automaton: CellularAutomation,
+    metadata: Metadata,
+}
+
+#[pymethods]
+impl PyCellularAutomation {
+    #[new]
+    fn new() -> Self {
+        Self {
+            automaton: CellularAutomation::new(),
+            metadata: Metadata::default(),
+        }
+    }
+
+    // Формат файла определяется по расширению, как и в приложении
+    #[staticmethod]
+    fn load(path: PathBuf) -> PyResult<Self> {
+        let document = files::load(&path).map_err(file_error)?;
+
+        Ok(Self {
+            automaton: document.automaton,
+            metadata: document.metadata,
+        })
+    }
+
+    fn save(&self, path: PathBuf) -> PyResult<()> {
+        files::save(&path, &self.automaton, &self.metadata).map_err(file_error)
+    }
+
+    fn copy(&self) -> Self {
+        self.clone()
+    }
+
+    #[getter]
+    fn tact(&self) -> u64 {
+        self.automaton.tact
+    }
+
+    // Расчёт n тактов. На время расчёта GIL отпускается.
+    #[pyo3(signature = (n = 1))]
+    fn step(&mut self, py: Python<'_>, n: u64) {
+        let automaton = &mut self.automaton;
+
+        py.detach(|| {
+            for _ in 0..n {
+                automaton.tact();
+            }
+        });
+    }
+
+    fn get_cell(&self, x: i64, y: i64) -> PyState {
+        PyState(
+            self.automaton
+                .try_get_cell((x, y))
+                .copied()
+                .unwrap_or_default(),
+        )
+    }
+
+    fn set_cell(&mut self, x: i64, y: i64, state: StateArg) -> PyResult<()> {
+        self.automaton.set_cell((x, y), state.state()?);
+
+        Ok(())
+    }
+
+    fn population(&self) -> usize {
+        self.automaton.population()
+    }
+
+    fn fingerprint(&self) -> u64 {
+        self.automaton.fingerprint()
+    }
+
+    // ((x0, y0), (x1, y1)) - углы прямоугольника с непустыми клетками или None
+    fn bounds(&self) -> Option<((i64, i64), (i64, i64))> {
+        self.automaton.bounds()
+    }
+
+    // Номера состояний клеток прямоугольника с углами (x0, y0) и (x1, y1) включительно
+    fn region<'py>(
+        &self,
+        py: Python<'py>,
+        x0: i64,
+        y0: i64,
+        x1: i64,
+        y1: i64,
+    ) -> PyResult<Bound<'py, PyArray2<u8>>> {
+        if x1 < x0 || y1 < y0 {
+            return Err(PyValueError::new_err(
+                "правый нижний угол области левее или выше левого верхнего",
+            ));
+        }
+
+        let (width, height) = ((x1 - x0 + 1) as usize, (y1 - y0 + 1) as usize);
+        let mut cells = Vec::with_capacity(width * height);
+
+        for y in y0..=y1 {
+            for x in x0..=x1 {
+                let state = self.automaton.try_get_cell((x, y)).copied();
+
+                cells.push(state.unwrap_or_default().index());
+            }
+        }
+
+        let array = Array2::from_shape_vec((height, width), cells)
+            .expect("размер массива совпадает с количеством клеток");
+
+        Ok(array.into_pyarray(py))
+    }
+
+    // Запись номеров состояний из массива в поле, начиная с клетки (x0, y0)
+    fn set_region(&mut self, x0: i64, y0: i64, cells: PyReadonlyArray2<'_, u8>) -> PyResult<()> {
+        let cells = cells.as_array();
+
+        for ((y, x), &index) in cells.indexed_iter() {
+            self.automaton
+                .set_cell((x0 + x as i64, y0 + y as i64), state_from_index(index)?);
+        }
+
+        Ok(())
+    }
+
+    fn __repr__(&self) -> String {
+        format!(
+            "CellularAutomation(tact={}, population={})",
+            self.automaton.tact,
+            self.automaton.population()
+        )
+    }
+}
+
+#[pyfunction]
+fn load(path: PathBuf) -> PyResult<PyCellularAutomation> {
+    PyCellularAutomation::load(path)
+}
+
+#[pymodule]
+fn kavn(m: &Bound<'_, PyModule>) -> PyResult<()> {
+    m.add_class::<PyDirection>()?;
+    m.add_class::<PyState>()?;
+    m.add_class::<PyCellularAutomation>()?;
+    m.add_function(wrap_pyfunction!(load, m)?)?;
+
+    Ok(())
+}
@@ -0,0 +1,405 @@
+mod transport;
+
+pub use transport::*;
+
+use crate::cellular_automaton::{CellularAutomation, Snapshot, State};
+use crate::files::{self, Document, Metadata};
+use serde::de::DeserializeOwned;
+use serde::Deserialize;
+use serde_json::{json, Value};
+use std::ops::Deref;
+use std::path::PathBuf;
+
+// Управление программой по JSON-RPC 2.0: один запрос или ответ в строке.
+//
+// Методы:
+//     load {path}                          загрузка поля из файла
+//     save {path}                          сохранение поля
+//     status                               {tact, population, paused, bounds}
+//     step {tacts = 1}                     расчёт тактов
+//     pause / resume                       остановка и запуск расчёта
+//     read_region {x0, y0, x1, y1}         {x0, y0, width, height, cells} - номера состояний по строкам
+//     set_cells {cells: [[x, y, state]]}   state - номер (0-28) или обозначение ("T000")
+//     snapshot                             {id, tact} - сохранение текущего поля
+//     restore {id}                         возврат к сохранённому полю
+//     snapshots                            [{id, tact}]
+
+// Коды ошибок из спецификации JSON-RPC
+const PARSE_ERROR: i64 = -32700;
+const INVALID_REQUEST: i64 = -32600;
+const METHOD_NOT_FOUND: i64 = -32601;
+const INVALID_PARAMS: i64 = -32602;
+// Ошибка при выполнении метода (например, при чтении файла)
+const SERVER_ERROR: i64 = -32000;
+
+// Наибольшее количество клеток, которое можно прочитать одним запросом
+const MAX_REGION: i64 = 1 << 24;
+
+// Поле хранится целиком, поэтому set_cells не может расширить его больше этой площади
+const MAX_FIELD_AREA: i64 = 1 << 26;
+
+// То, чем управляет сервер: поле в окне или поле без окна
+pub trait Simulator {
+    // Поле для чтения (status, read_region, save и т.п.) ничего не меняет в окне,
+    // а после изменения через automaton_mut окно считает поле изменённым
+    fn automaton(&self) -> impl Deref<Target = CellularAutomation> + '_;
+    fn automaton_mut<R>(&mut self, f: impl FnOnce(&mut CellularAutomation) -> R) -> R;
+    fn metadata(&self) -> Metadata;
+
+    // Расчёт тактов и изменение клеток так же, как из интерфейса (с историей и тепловой картой).
+    // step возвращает номер такта после расчёта.
+    fn step(&mut self, tacts: u64) -> u64 {
+        self.automaton_mut(|ca| {
+            for _ in 0..tacts {
+                ca.tact();
+            }
+
+            ca.tact
+        })
+    }
+
+    fn set_cells(&mut self, cells: Vec<((i64, i64), State)>) {
+        self.automaton_mut(|ca| {
+            for (coords, state) in cells {
+                ca.set_cell(coords, state);
+            }
+        })
+    }
+
+    // Замена поля целиком. Если описание не передано, остаётся прежнее.
+    fn replace(&mut self, automaton: CellularAutomation, metadata: Option<Metadata>);
+
+    fn paused(&self) -> bool;
+    fn set_paused(&mut self, paused: bool);
+}
+
+// Поле для работы без окна
+pub struct Headless {
+    pub automaton: CellularAutomation,
+    pub metadata: Metadata,
+    pub paused: bool,
+}
+
+impl Simulator for Headless {
+    fn automaton(&self) -> impl Deref<Target = CellularAutomation> + '_ {
+        &self.automaton
+    }
+
+    fn automaton_mut<R>(&mut self, f: impl FnOnce(&mut CellularAutomation) -> R) -> R {
+        f(&mut self.automaton)
+    }
+
+    fn metadata(&self) -> Metadata {
+        self.metadata.clone()
+    }
+
+    fn replace(&mut self, automaton: CellularAutomation, metadata: Option<Metadata>) {
+        self.automaton = automaton;
+
+        if let Some(metadata) = metadata {
+            self.metadata = metadata;
+        }
+    }
+
+    fn paused(&self) -> bool {
+        self.paused
+    }
+
+    fn set_paused(&mut self, paused: bool) {
+        self.paused = paused;
+    }
+}
+
+struct RpcError {
+    code: i64,
+    message: String,
+}
+
+impl RpcError {
+    fn new(code: i64, message: impl Into<String>) -> Self {
+        Self {
+            code,
+            message: message.into(),
+        }
+    }
+}
+
+#[derive(Deserialize)]
+struct PathParams {
+    path: PathBuf,
+}
+
+#[derive(Deserialize)]
+struct StepParams {
+    #[serde(default = "one")]
+    tacts: u64,
+}
+
+fn one() -> u64 {
+    1
+}
+
+#[derive(Deserialize)]
+struct RegionParams {
+    x0: i64,
+    y0: i64,
+    x1: i64,
+    y1: i64,
+}
+
+#[derive(Deserialize)]
+#[serde(untagged)]
+enum StateParam {
+    Index(u8),
+    Notation(String),
+}
+
+#[derive(Deserialize)]
+struct SetCellsParams {
+    cells: Vec<(i64, i64, StateParam)>,
+}
+
+#[derive(Deserialize)]
+struct RestoreParams {
+    id: usize,
+}
+
+fn params<T: DeserializeOwned>(params: Value) -> Result<T, RpcError> {
+    // Параметры можно не указывать, если у метода нет обязательных параметров
+    let params = if params.is_null() { json!({}) } else { params };
+
+    serde_json::from_value(params).map_err(|err| RpcError::new(INVALID_PARAMS, err.to_string()))
+}
+
+// Сервер хранит только снимки поля, само поле принадлежит Simulator
+#[derive(Default)]
+pub struct Server {
+    snapshots: Vec<Snapshot>,
+}
+
+impl Server {
+    // Обработка строки с запросом. Для уведомлений (запросов без id) ответа нет.
+    pub fn handle(&mut self, simulator: &mut impl Simulator, line: &str) -> Option<String> {
+        let request: Value = match serde_json::from_str(line) {
+            Ok(request) => request,
+            Err(err) => {
+                return Some(response(
+                    Value::Null,
+                    Err(RpcError::new(PARSE_ERROR, err.to_string())),
+                ))
+            }
+        };
+
+        let id = request.get("id").cloned();
+
+        let result = match request.get("method").and_then(Value::as_str) {
+            None => Err(RpcError::new(INVALID_REQUEST, "не указан метод")),
+            Some(method) => {
+                let params = request.get("params").cloned().unwrap_or(Value::Null);
+
+                self.call(simulator, method, params)
+            }
+        };
+
+        id.map(|id| response(id, result))
+    }
+
+    fn call(
+        &mut self,
+        simulator: &mut impl Simulator,
+        method: &str,
+        params: Value,
+    ) -> Result<Value, RpcError> {
+        match method {
+            "load" => {
+                let PathParams { path } = self::params(params)?;
+
+                let Document {
+                    automaton,
+                    metadata,
+                } = files::load(&path)
+                    .map_err(|err| RpcError::new(SERVER_ERROR, err.to_string()))?;
+
+                simulator.replace(automaton, Some(metadata));
+
+                Ok(status(simulator))
+            }
+
+            "save" => {
+                let PathParams { path } = self::params(params)?;
+                let metadata = simulator.metadata();
+
+                files::save(&path, &simulator.automaton(), &metadata)
+                    .map_err(|err| RpcError::new(SERVER_ERROR, err.to_string()))?;
+
+                Ok(Value::Null)
+            }
+
+            "status" => Ok(status(simulator)),
+
+            "step" => {
+                let StepParams { tacts } = self::params(params)?;
+
+                let tact = simulator.step(tacts);
+
+                Ok(json!({ "tact": tact }))
+            }
+
+            "pause" | "resume" => {
+                simulator.set_paused(method == "pause");
+
+                Ok(status(simulator))
+            }
+
+            "read_region" => {
+                let RegionParams { x0, y0, x1, y1 } = self::params(params)?;
+
+                let (width, height) = (
+                    x1.saturating_sub(x0).saturating_add(1),
+                    y1.saturating_sub(y0).saturating_add(1),
+                );
+
+                if width <= 0 || height <= 0 || width.saturating_mul(height) > MAX_REGION {
+                    return Err(RpcError::new(
+                        INVALID_PARAMS,
+                        format!("неверный размер области {}x{}", width, height),
+                    ));
+                }
+
+                let ca = simulator.automaton();
+                let cells: Vec<Vec<u8>> = (y0..=y1)
+                    .map(|y| {
+                        (x0..=x1)
+                            .map(|x| ca.try_get_cell((x, y)).copied().unwrap_or_default().index())
+                            .collect()
+                    })
+                    .collect();
+
+                Ok(json!({
+                    "x0": x0,
+                    "y0": y0,
+                    "width": width,
+                    "height": height,
+                    "cells": cells,
+                }))
+            }
+
+            "set_cells" => {
+                let SetCellsParams { cells } = self::params(params)?;
+
+                // Сначала проверяются все состояния, чтобы не применять запрос частично
+                let cells = cells
+                    .into_iter()
+                    .map(|(x, y, state)| {
+                        let parsed = match &state {
+                            StateParam::Index(index) => State::from_index(*index),
+                            StateParam::Notation(notation) => State::from_notation(notation),
+                        };
+
+                        parsed.map(|state| ((x, y), state)).ok_or_else(|| {
+                            RpcError::new(
+                                INVALID_PARAMS,
+                                format!("неизвестное состояние клетки ({}, {})", x, y),
+                            )
+                        })
+                    })
+                    .collect::<Result<Vec<_>, _>>()?;
+
+                // Далёкие клетки растянули бы поле до размеров, которые не помещаются в память
+                let bounds = simulator.automaton().bounds();
+                let ((x0, y0), (x1, y1)) = cells
+                    .iter()
+                    .fold(bounds, |bounds, &((x, y), _)| match bounds {
+                        None => Some(((x, y), (x, y))),
+                        Some(((x0, y0), (x1, y1))) => {
+                            Some(((x0.min(x), y0.min(y)), (x1.max(x), y1.max(y))))
+                        }
+                    })
+                    .unwrap_or(((0, 0), (0, 0)));
+
+                let (width, height) = (
+                    x1.saturating_sub(x0).saturating_add(1),
+                    y1.saturating_sub(y0).saturating_add(1),
+                );
+
+                if width.saturating_mul(height) > MAX_FIELD_AREA {
+                    return Err(RpcError::new(
+                        INVALID_PARAMS,
+                        format!(
+                            "клетки слишком далеко друг от друга: поле {}x{} не поместится в память",
+                            width, height
+                        ),
+                    ));
+                }
+
+                let count = cells.len();
+
+                simulator.set_cells(cells);
+
+                Ok(json!({ "count": count }))
+            }
+
+            "snapshot" => {
+                let snapshot = Snapshot::new(&simulator.automaton());
+                let tact = snapshot.tact();
+
+                self.snapshots.push(snapshot);
+
+                Ok(json!({ "id": self.snapshots.len() - 1, "tact": tact }))
+            }
+
+            "restore" => {
+                let RestoreParams { id } = self::params(params)?;
+
+                let snapshot = self
+                    .snapshots
+                    .get(id)
+                    .ok_or_else(|| RpcError::new(INVALID_PARAMS, format!("снимка {} нет", id)))?;
+
+                let restored = snapshot
+                    .restore()
+                    .map_err(|err| RpcError::new(SERVER_ERROR, err.to_string()))?;
+
+                simulator.replace(restored, None);
+
+                Ok(status(simulator))
+            }
+
+            "snapshots" => Ok(self
+                .snapshots
+                .iter()
+                .enumerate()
+                .map(|(id, snapshot)| json!({ "id": id, "tact": snapshot.tact() }))
+                .collect()),
+
+            _ => Err(RpcError::new(
+                METHOD_NOT_FOUND,
+                format!("неизвестный метод \"{}\"", method),
+            )),
+        }
+    }
+}
+
+fn status(simulator: &impl Simulator) -> Value {
+    let ca = simulator.automaton();
+
+    json!({
+        "tact": ca.tact,
+        "population": ca.population(),
+        "paused": simulator.paused(),
+        "bounds": ca.bounds(),
+    })
+}
+
+fn response(id: Value, result: Result<Value, RpcError>) -> String {
+    let response = match result {
+        Ok(result) => json!({ "jsonrpc": "2.0", "id": id, "result": result }),
+        Err(RpcError { code, message }) => json!({
+            "jsonrpc": "2.0",
+            "id": id,
+            "error": { "code": code, "message": message },
+        }),
+    };
+
+    response.to_string()
+}
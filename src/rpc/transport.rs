@@ -0,0 +1,85 @@
+use std::io::{self, BufRead, BufReader, Write};
+use std::sync::mpsc::{channel, Receiver, Sender};
+use std::thread;
+
+// Строка запроса вместе с тем, куда нужно записать ответ
+pub struct Request {
+    pub line: String,
+    pub reply: Box<dyn Write + Send>,
+}
+
+impl Request {
+    pub fn respond(mut self, response: &str) {
+        // Клиент мог уже отключиться, тогда ответ просто теряется
+        let _ = writeln!(self.reply, "{}", response).and_then(|_| self.reply.flush());
+    }
+}
+
+// Чтение запросов в отдельном потоке, чтобы основной цикл (окно или расчёт) не блокировался
+fn read_lines(
+    reader: impl BufRead + Send + 'static,
+    reply: impl Fn() -> io::Result<Box<dyn Write + Send>> + Send + 'static,
+    sender: Sender<Request>,
+) {
+    thread::spawn(move || {
+        for line in reader.lines() {
+            let (Ok(line), Ok(reply)) = (line, reply()) else {
+                break;
+            };
+
+            if line.trim().is_empty() {
+                continue;
+            }
+
+            if sender.send(Request { line, reply }).is_err() {
+                break;
+            }
+        }
+    });
+}
+
+// Запросы из stdin, ответы в stdout. Когда stdin закрывается, закрывается и канал.
+pub fn stdio() -> Receiver<Request> {
+    let (sender, receiver) = channel();
+
+    read_lines(
+        BufReader::new(io::stdin()),
+        || Ok(Box::new(io::stdout())),
+        sender,
+    );
+
+    receiver
+}
+
+// Запросы от любого количества клиентов, подключённых к Unix-сокету
+#[cfg(unix)]
+pub fn unix_socket(path: &std::path::Path) -> io::Result<Receiver<Request>> {
+    use std::os::unix::fs::FileTypeExt;
+    use std::os::unix::net::UnixListener;
+
+    // Сокет, оставшийся от предыдущего запуска, мешает создать новый
+    if let Ok(metadata) = std::fs::metadata(path) {
+        if metadata.file_type().is_socket() {
+            std::fs::remove_file(path)?;
+        }
+    }
+
+    let listener = UnixListener::bind(path)?;
+    let (sender, receiver) = channel();
+
+    thread::spawn(move || {
+        for stream in listener.incoming().flatten() {
+            let Ok(writer) = stream.try_clone() else {
+                continue;
+            };
+
+            read_lines(
+                BufReader::new(stream),
+                move || Ok(Box::new(writer.try_clone()?)),
+                sender.clone(),
+            );
+        }
+    });
+
+    Ok(receiver)
+}
@@ -0,0 +1,341 @@
+use crate::cellular_automaton::{CellularAutomation, Region, State};
+use crate::files::{self, Document, Metadata};
+use rhai::{Array, Dynamic, Engine, EvalAltResult};
+use std::cell::RefCell;
+use std::path::Path;
+use std::rc::Rc;
+
+// Скрипты на Rhai для повторяющихся действий с полем.
+// Скрипт работает с копией состояния программы, которое после выполнения применяется обратно.
+//
+// Доступные функции:
+//     get_cell(x, y) -> "T000"          get_index(x, y) -> 13
+//     set_cell(x, y, "T000")            set_cell(x, y, 13)
+//     fill(x0, y0, x1, y1, состояние)   clear()
+//     tact()  tact(n)  current_tact()   population()
+//     selection() -> [x0, y0, x1, y1] или ()
+//     select(x0, y0, x1, y1)            clear_selection()
+//     load("поле.rle")  save("поле.data")
+//     camera() -> [x, y]  center_on(x, y)  zoom(размер клетки)  (только в окне)
+//
+// Чтобы зациклившийся скрипт не повесил окно, число операций ограничено.
+// Такт и каждая клетка, заполненная fill, считаются отдельной операцией.
+
+type Result<T> = std::result::Result<T, Box<EvalAltResult>>;
+
+const MAX_OPERATIONS: u64 = 10_000_000;
+
+pub struct Script {
+    pub automaton: CellularAutomation,
+    pub metadata: Metadata,
+    pub selection: Option<Region>,
+
+    // Клетка в центре экрана и размер клетки в пикселях
+    pub camera: (i64, i64),
+    pub cell_size: f32,
+
+    // Если true, вывод print сразу печатается в консоль, иначе собирается в output
+    pub echo: bool,
+    pub output: Vec<String>,
+
+    // Наибольшее число операций (0 - без ограничения) и операции,
+    // выполненные встроенными функциями (такты, заполнение)
+    pub max_operations: u64,
+    work: u64,
+}
+
+impl Script {
+    pub fn new(automaton: CellularAutomation, metadata: Metadata) -> Self {
+        Self {
+            automaton,
+            metadata,
+            selection: None,
+            camera: (0, 0),
+            cell_size: 50.0,
+            echo: false,
+            output: vec![],
+            max_operations: MAX_OPERATIONS,
+            work: 0,
+        }
+    }
+
+    fn over_limit(&self, operations: u64) -> bool {
+        self.max_operations > 0 && operations.saturating_add(self.work) > self.max_operations
+    }
+
+    // Учёт работы встроенной функции до её выполнения
+    fn spend(&mut self, work: u64) -> Result<()> {
+        if self.over_limit(work) {
+            return Err(limit_message(self.max_operations).into());
+        }
+
+        self.work += work;
+
+        Ok(())
+    }
+
+    fn print(&mut self, text: &str) {
+        if self.echo {
+            println!("{}", text);
+        } else {
+            self.output.push(text.to_string());
+        }
+    }
+}
+
+// Выполнение скрипта. Состояние возвращается и в случае ошибки, так как до неё
+// скрипт мог успеть что-то изменить.
+pub fn run(source: &str, script: Script) -> (Script, std::result::Result<(), String>) {
+    let max_operations = script.max_operations;
+    let state = Rc::new(RefCell::new(script));
+
+    let result = engine(&state).run(source).map_err(|err| match *err {
+        EvalAltResult::ErrorTerminated(..) => limit_message(max_operations),
+        err => err.to_string(),
+    });
+
+    let script = Rc::try_unwrap(state)
+        .ok()
+        .expect("движок скрипта удалён, других ссылок на состояние нет")
+        .into_inner();
+
+    (script, result)
+}
+
+fn limit_message(max_operations: u64) -> String {
+    format!(
+        "скрипт остановлен: превышено ограничение в {} операций",
+        max_operations
+    )
+}
+
+fn state_from_notation(notation: &str) -> Result<State> {
+    State::from_notation(notation)
+        .ok_or_else(|| format!("неизвестное состояние \"{}\"", notation).into())
+}
+
+fn state_from_index(index: i64) -> Result<State> {
+    u8::try_from(index)
+        .ok()
+        .and_then(State::from_index)
+        .ok_or_else(|| format!("неизвестный номер состояния {}", index).into())
+}
+
+fn engine(state: &Rc<RefCell<Script>>) -> Engine {
+    let mut engine = Engine::new();
+
+    let s = state.clone();
+    engine.on_print(move |text| s.borrow_mut().print(text));
+
+    let s = state.clone();
+    engine.on_debug(move |text, _, _| s.borrow_mut().print(text));
+
+    let s = state.clone();
+    engine
+        .on_progress(move |operations| s.borrow().over_limit(operations).then_some(Dynamic::UNIT));
+
+    // Клетки
+
+    let s = state.clone();
+    engine.register_fn("get_cell", move |x: i64, y: i64| {
+        let script = s.borrow();
+        let state = script.automaton.try_get_cell((x, y)).copied();
+
+        state.unwrap_or_default().notation()
+    });
+
+    let s = state.clone();
+    engine.register_fn("get_index", move |x: i64, y: i64| {
+        let script = s.borrow();
+        let state = script.automaton.try_get_cell((x, y)).copied();
+
+        state.unwrap_or_default().index() as i64
+    });
+
+    let s = state.clone();
+    engine.register_fn(
+        "set_cell",
+        move |x: i64, y: i64, notation: &str| -> Result<()> {
+            let state = state_from_notation(notation)?;
+
+            s.borrow_mut().automaton.set_cell((x, y), state);
+
+            Ok(())
+        },
+    );
+
+    let s = state.clone();
+    engine.register_fn(
+        "set_cell",
+        move |x: i64, y: i64, index: i64| -> Result<()> {
+            let state = state_from_index(index)?;
+
+            s.borrow_mut().automaton.set_cell((x, y), state);
+
+            Ok(())
+        },
+    );
+
+    let s = state.clone();
+    engine.register_fn(
+        "fill",
+        move |x0: i64, y0: i64, x1: i64, y1: i64, notation: &str| -> Result<()> {
+            let state = state_from_notation(notation)?;
+            let script = &mut *s.borrow_mut();
+
+            let area = (x0.abs_diff(x1) + 1).saturating_mul(y0.abs_diff(y1) + 1);
+            script.spend(area)?;
+
+            let ca = &mut script.automaton;
+
+            for y in y0.min(y1)..=y0.max(y1) {
+                for x in x0.min(x1)..=x0.max(x1) {
+                    ca.set_cell((x, y), state);
+                }
+            }
+
+            Ok(())
+        },
+    );
+
+    let s = state.clone();
+    engine.register_fn("clear", move || {
+        let script = &mut s.borrow_mut();
+
+        script.automaton = CellularAutomation {
+            tact: script.automaton.tact,
+            ..CellularAutomation::new()
+        };
+    });
+
+    // Такты
+
+    let s = state.clone();
+    engine.register_fn("tact", move || -> Result<()> {
+        let script = &mut *s.borrow_mut();
+
+        script.spend(1)?;
+        script.automaton.tact();
+
+        Ok(())
+    });
+
+    let s = state.clone();
+    engine.register_fn("tact", move |n: i64| -> Result<()> {
+        let script = &mut *s.borrow_mut();
+
+        script.spend(n.max(0) as u64)?;
+
+        for _ in 0..n {
+            script.automaton.tact();
+        }
+
+        Ok(())
+    });
+
+    let s = state.clone();
+    engine.register_fn("current_tact", move || s.borrow().automaton.tact as i64);
+
+    let s = state.clone();
+    engine.register_fn("population", move || {
+        s.borrow().automaton.population() as i64
+    });
+
+    // Выделение
+
+    let s = state.clone();
+    engine.register_fn("selection", move || match s.borrow().selection {
+        None => Dynamic::UNIT,
+        Some(((x0, y0), (x1, y1))) => Dynamic::from_array(
+            [x0, y0, x1, y1]
+                .into_iter()
+                .map(Dynamic::from_int)
+                .collect::<Array>(),
+        ),
+    });
+
+    let s = state.clone();
+    engine.register_fn("select", move |x0: i64, y0: i64, x1: i64, y1: i64| {
+        s.borrow_mut().selection = Some(((x0.min(x1), y0.min(y1)), (x0.max(x1), y0.max(y1))));
+    });
+
+    let s = state.clone();
+    engine.register_fn("clear_selection", move || s.borrow_mut().selection = None);
+
+    // Файлы
+
+    let s = state.clone();
+    engine.register_fn("load", move |path: &str| -> Result<()> {
+        let Document {
+            automaton,
+            metadata,
+        } = files::load(Path::new(path)).map_err(|err| err.to_string())?;
+
+        let script = &mut s.borrow_mut();
+
+        script.automaton = automaton;
+        script.metadata = metadata;
+
+        Ok(())
+    });
+
+    let s = state.clone();
+    engine.register_fn("save", move |path: &str| -> Result<()> {
+        let script = s.borrow();
+
+        files::save(Path::new(path), &script.automaton, &script.metadata)
+            .map_err(|err| err.to_string().into())
+    });
+
+    // Камера
+
+    let s = state.clone();
+    engine.register_fn("camera", move || {
+        let (x, y) = s.borrow().camera;
+
+        vec![Dynamic::from_int(x), Dynamic::from_int(y)]
+    });
+
+    let s = state.clone();
+    engine.register_fn("center_on", move |x: i64, y: i64| {
+        s.borrow_mut().camera = (x, y);
+    });
+
+    let s = state.clone();
+    engine.register_fn("zoom", move |cell_size: f64| {
+        s.borrow_mut().cell_size = cell_size as f32;
+    });
+
+    let s = state.clone();
+    engine.register_fn("zoom", move |cell_size: i64| {
+        s.borrow_mut().cell_size = cell_size as f32;
+    });
+
+    engine
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn run_limited(source: &str) -> std::result::Result<(), String> {
+        let mut script = Script::new(CellularAutomation::new(), Metadata::default());
+        script.max_operations = 1000;
+
+        run(source, script).1
+    }
+
+    #[test]
+    fn endless_loop_is_stopped() {
+        let err = run_limited("loop { }").unwrap_err();
+
+        assert!(err.contains("ограничение в 1000 операций"), "{}", err);
+    }
+
+    #[test]
+    fn builtin_work_is_counted() {
+        assert!(run_limited("tact(1000000000000)").is_err());
+        assert!(run_limited("fill(0, 0, 100000, 100000, \"C00\")").is_err());
+        assert!(run_limited("tact(10); fill(0, 0, 9, 9, \"C00\")").is_ok());
+    }
+}
@@ -0,0 +1,308 @@
+use crate::cellular_automaton::{CellularAutomation, Direction, State};
+use crate::files::{self, Document, Metadata};
+use crossterm::event::{self, Event, KeyCode, KeyEvent, KeyEventKind};
+use crossterm::style::{Attribute, Color, Print, ResetColor, SetAttribute, SetForegroundColor};
+use crossterm::{cursor, execute, queue, terminal};
+use std::io::{self, Write};
+use std::path::PathBuf;
+use std::time::Duration;
+
+// Просмотр и редактирование поля в терминале (например, при работе по SSH).
+// Клетка занимает два символа по горизонтали, чтобы поле не казалось сжатым.
+
+const HELP: &str = "Стрелки - курсор  Пробел - пауза  N - шаг  +/- - скорость  Tab - кисть  \
+                    Enter - поставить  Del - стереть  C - к курсору  W - сохранить  Q - выход";
+
+// Время ожидания нажатия клавиши между кадрами
+const FRAME: Duration = Duration::from_millis(50);
+
+struct Tui {
+    automaton: CellularAutomation,
+    metadata: Metadata,
+
+    // Куда сохранять поле по W
+    path: Option<PathBuf>,
+
+    // Клетка в левом верхнем углу экрана и клетка под курсором
+    view: (i64, i64),
+    cursor: (i64, i64),
+
+    paused: bool,
+    speed: u64,
+
+    // Номер состояния, которое ставится по Enter
+    brush: u8,
+
+    message: String,
+}
+
+// Возвращает терминал в обычный режим даже при ошибке или панике
+struct RawMode;
+
+impl RawMode {
+    fn enable() -> io::Result<Self> {
+        terminal::enable_raw_mode()?;
+        execute!(io::stdout(), terminal::EnterAlternateScreen, cursor::Hide)?;
+
+        Ok(RawMode)
+    }
+}
+
+impl Drop for RawMode {
+    fn drop(&mut self) {
+        let _ = execute!(io::stdout(), cursor::Show, terminal::LeaveAlternateScreen);
+        let _ = terminal::disable_raw_mode();
+    }
+}
+
+pub fn run(document: Document, path: Option<PathBuf>) -> io::Result<()> {
+    let Document {
+        automaton,
+        metadata,
+    } = document;
+
+    let mut tui = Tui {
+        automaton,
+        metadata,
+        path,
+        view: (0, 0),
+        cursor: (0, 0),
+        paused: true,
+        speed: 1,
+        // Обычное передающее вправо
+        brush: 13,
+        message: String::new(),
+    };
+
+    let _raw_mode = RawMode::enable()?;
+
+    if let Some(((x0, y0), (x1, y1))) = tui.automaton.bounds() {
+        tui.center_on(((x0 + x1) / 2, (y0 + y1) / 2))?;
+    }
+
+    loop {
+        tui.draw()?;
+
+        if event::poll(FRAME)? {
+            if let Event::Key(key) = event::read()? {
+                if key.kind == KeyEventKind::Press && !tui.handle_key(key)? {
+                    break;
+                }
+            }
+        }
+
+        if !tui.paused {
+            for _ in 0..tui.speed {
+                tui.automaton.tact();
+            }
+        }
+    }
+
+    Ok(())
+}
+
+impl Tui {
+    // Размер видимой части поля в клетках (две нижние строки заняты подсказками).
+    // Хотя бы одна клетка, даже если терминал слишком мал.
+    fn field_size() -> io::Result<(i64, i64)> {
+        let (width, height) = terminal::size()?;
+
+        Ok((
+            (width / 2).max(1) as i64,
+            height.saturating_sub(2).max(1) as i64,
+        ))
+    }
+
+    fn center_on(&mut self, (x, y): (i64, i64)) -> io::Result<()> {
+        let (width, height) = Self::field_size()?;
+
+        self.cursor = (x, y);
+        self.view = (x - width / 2, y - height / 2);
+
+        Ok(())
+    }
+
+    // Сдвиг поля так, чтобы курсор оставался на экране
+    fn follow_cursor(&mut self) -> io::Result<()> {
+        let (width, height) = Self::field_size()?;
+        let (x, y) = self.cursor;
+
+        self.view.0 = self.view.0.clamp(x - width + 1, x);
+        self.view.1 = self.view.1.clamp(y - height + 1, y);
+
+        Ok(())
+    }
+
+    // Возвращает false, если нужно выйти
+    fn handle_key(&mut self, key: KeyEvent) -> io::Result<bool> {
+        self.message.clear();
+
+        match key.code {
+            KeyCode::Char('q') | KeyCode::Esc => return Ok(false),
+
+            KeyCode::Up | KeyCode::Char('k') => self.cursor.1 -= 1,
+            KeyCode::Down | KeyCode::Char('j') => self.cursor.1 += 1,
+            KeyCode::Left | KeyCode::Char('h') => self.cursor.0 -= 1,
+            KeyCode::Right | KeyCode::Char('l') => self.cursor.0 += 1,
+
+            KeyCode::Char(' ') => self.paused = !self.paused,
+            KeyCode::Char('n') => {
+                self.paused = true;
+                self.automaton.tact();
+            }
+            KeyCode::Char('+') | KeyCode::Char('=') => self.speed += 1,
+            KeyCode::Char('-') => self.speed = (self.speed - 1).max(1),
+
+            KeyCode::Tab => self.brush = (self.brush + 1) % 29,
+            KeyCode::BackTab => self.brush = (self.brush + 28) % 29,
+
+            KeyCode::Enter => {
+                let state = State::from_index(self.brush).unwrap_or_default();
+
+                self.automaton.set_cell(self.cursor, state);
+            }
+            KeyCode::Delete | KeyCode::Backspace | KeyCode::Char('x') => {
+                self.automaton.set_cell(self.cursor, State::Unexcitable);
+            }
+
+            KeyCode::Char('c') => self.center_on(self.cursor)?,
+
+            KeyCode::Char('w') => self.save(),
+
+            _ => {}
+        }
+
+        self.follow_cursor()?;
+
+        Ok(true)
+    }
+
+    fn save(&mut self) {
+        self.message = match &self.path {
+            None => "Файл для сохранения не указан (kavn tui <файл>)".to_string(),
+            Some(path) => match files::save(path, &self.automaton, &self.metadata) {
+                Ok(()) => format!("Сохранено в {}", path.display()),
+                Err(err) => err.to_string(),
+            },
+        };
+    }
+
+    fn draw(&self) -> io::Result<()> {
+        let (columns, _) = terminal::size()?;
+        let (width, height) = Self::field_size()?;
+        let mut out = io::stdout().lock();
+
+        for row in 0..height {
+            queue!(out, cursor::MoveTo(0, row as u16))?;
+
+            for column in 0..width {
+                let coords = (self.view.0 + column, self.view.1 + row);
+                let state = self
+                    .automaton
+                    .try_get_cell(coords)
+                    .copied()
+                    .unwrap_or_default();
+                let (glyph, color) = glyph(state);
+
+                if coords == self.cursor {
+                    queue!(out, SetAttribute(Attribute::Reverse))?;
+                }
+
+                queue!(out, SetForegroundColor(color), Print(glyph))?;
+
+                if coords == self.cursor {
+                    queue!(out, SetAttribute(Attribute::NoReverse))?;
+                }
+
+                queue!(out, Print(' '))?;
+            }
+
+            queue!(out, terminal::Clear(terminal::ClearType::UntilNewLine))?;
+        }
+
+        let under_cursor = self
+            .automaton
+            .try_get_cell(self.cursor)
+            .copied()
+            .unwrap_or_default();
+        let brush = State::from_index(self.brush).unwrap_or_default();
+
+        let status = format!(
+            "Такт {}  Клеток {}  {}  Скорость {}  ({}, {}): {}  Кисть: {} {}  {}",
+            self.automaton.tact,
+            self.automaton.population(),
+            if self.paused {
+                "Пауза"
+            } else {
+                "Идёт"
+            },
+            self.speed,
+            self.cursor.0,
+            self.cursor.1,
+            under_cursor.notation(),
+            glyph(brush).0,
+            brush.notation(),
+            self.message
+        );
+
+        queue!(
+            out,
+            ResetColor,
+            cursor::MoveTo(0, height as u16),
+            SetAttribute(Attribute::Bold),
+            Print(fit(&status, columns)),
+            SetAttribute(Attribute::Reset),
+            terminal::Clear(terminal::ClearType::UntilNewLine),
+            cursor::MoveTo(0, height as u16 + 1),
+            Print(fit(HELP, columns)),
+            terminal::Clear(terminal::ClearType::UntilNewLine),
+        )?;
+
+        out.flush()
+    }
+}
+
+// Обрезка строки по ширине терминала, чтобы она не переносилась и не сдвигала экран
+fn fit(text: &str, columns: u16) -> String {
+    text.chars().take(columns as usize).collect()
+}
+
+// Символ и цвет состояния: стрелки для передающих (двойные для специальных),
+// ■ для конфлюентных, цифры от 0 до 7 для чувствительных (в порядке S, S0, S1, ... S000).
+// Возбуждённые состояния выделяются жёлтым.
+fn glyph(state: State) -> (char, Color) {
+    let excited = |signal: bool, color: Color| if signal { Color::Yellow } else { color };
+
+    match state {
+        State::Unexcitable => ('·', Color::DarkGrey),
+
+        State::Sensitive { value } => (
+            char::from_digit(value as u32 - 1, 10).unwrap_or('?'),
+            Color::Magenta,
+        ),
+
+        State::NormalTransmitting { dir, signal } => {
+            let arrow = match dir {
+                Direction::Up => '↑',
+                Direction::Right => '→',
+                Direction::Down => '↓',
+                Direction::Left => '←',
+            };
+
+            (arrow, excited(signal, Color::Cyan))
+        }
+
+        State::SpecialTransmitting { dir, signal } => {
+            let arrow = match dir {
+                Direction::Up => '⇑',
+                Direction::Right => '⇒',
+                Direction::Down => '⇓',
+                Direction::Left => '⇐',
+            };
+
+            (arrow, excited(signal, Color::Red))
+        }
+
+        State::Confluence { new, old } => ('■', excited(new || old, Color::Green)),
+    }
+}
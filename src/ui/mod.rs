@@ -1,16 +1,26 @@
-use crate::cellular_automaton::{CellularAutomation, Direction, State, State::*};
-use crate::draw::CellularAutomatonPainter;
+use crate::cellular_automaton::{
+    self, CellularAutomation, Difference, Direction, Region, Snapshot, State, State::*,
+};
+use crate::draw::{self, CellularAutomatonPainter, Heatmap, Theme};
+use crate::export::{self, AnimationOptions, ImageOptions};
+use crate::files::{self, Bookmark, Document, Metadata, Viewport};
+use crate::rpc::{self, Simulator};
+use crate::script::{self, Script};
+use crate::settings::Settings;
 use crate::MouseButton;
 use egui::{CtxRef, Ui};
 use macroquad::input::{is_key_down, KeyCode};
-use macroquad::prelude::{is_mouse_button_pressed, mouse_position, mouse_wheel};
-use native_dialog::FileDialog;
+use macroquad::prelude::{
+    is_mouse_button_down, is_mouse_button_pressed, mouse_position, mouse_wheel, Color,
+};
+use native_dialog::{FileDialog, MessageDialog, MessageType};
 use std::collections::VecDeque;
-use std::fs::File;
-use std::io::{Read, Write};
+use std::fmt::Display;
 use std::ops::Deref;
 use std::rc::Rc;
+use std::sync::mpsc::Receiver;
 use std::sync::RwLock;
+use std::time::{Duration, Instant};
 
 pub struct UI {
     cellular_automation: Rc<RwLock<CellularAutomation>>,
@@ -22,15 +32,56 @@ pub struct UI {
     f_redact_field: bool,
 
     f_signal: bool,
+    f_show_metadata: bool,
+    f_export_selection: bool,
+    f_show_console: bool,
+    f_show_heatmap: bool,
+    f_show_minimap: bool,
+    f_minimap_drag: bool,
+    f_show_rulers: bool,
+    f_differences_outdated: bool,
 
     // Значения
     input_tact_text: String,
     input_tact: u64,
     speed: u64,
     selected_state: State,
+    input_bookmark_name: String,
+    goto: (i64, i64),
+    export_options: ImageOptions,
+    animation_options: AnimationOptions,
+
+    // Выделенная область и клетка, с которой началось выделение
+    selection: Option<Region>,
+    selection_start: Option<(i64, i64)>,
+
+    // Описание открытого поля
+    metadata: Metadata,
 
     // История состояния полей (каждых 200 тактов)
-    history: VecDeque<CellularAutomation>,
+    history: VecDeque<Snapshot>,
+
+    // Поле, с которым сравнивается текущее, и найденные отличия
+    diff_reference: Option<CellularAutomation>,
+    differences: Vec<((i64, i64), Difference)>,
+
+    // Текст скрипта в консоли и вывод выполненных скриптов
+    script_source: String,
+    script_output: Vec<String>,
+
+    // Мини-карта в текущем кадре (нет, если она скрыта или поле пустое)
+    minimap: Option<draw::Minimap>,
+
+    // Источник запросов JSON-RPC и сервер, который их выполняет
+    rpc: Option<(Receiver<rpc::Request>, rpc::Server)>,
+
+    settings: Settings,
+
+    // Когда настройки последний раз изменились, если они ещё не сохранены
+    settings_changed: Option<Instant>,
+
+    // Активность клеток, накопленная за время показа тепловой карты
+    heatmap: Heatmap,
 }
 
 impl UI {
@@ -40,38 +91,102 @@ impl UI {
             ca.tact
         };
 
-        let start = (*cellular_automation.read().unwrap().deref()).clone();
+        let start = Snapshot::new(&cellular_automation.read().unwrap());
+        let settings = Settings::load();
+
+        let mut painter = CellularAutomatonPainter::new(cellular_automation.clone(), (50.0, 50.0));
+        painter.theme = settings.theme;
 
         Self {
             cellular_automation: cellular_automation.clone(),
-            painter: CellularAutomatonPainter::new(cellular_automation.clone(), (50.0, 50.0)),
+            painter,
 
             f_redact_tact: false,
             f_pause: true,
             f_redact_field: false,
 
             f_signal: false,
+            f_show_metadata: false,
+            f_export_selection: false,
+            f_show_console: false,
+            f_show_heatmap: false,
+            f_show_minimap: true,
+            f_minimap_drag: false,
+            f_show_rulers: true,
+            f_differences_outdated: false,
 
             input_tact_text: input_tact.to_string(),
             input_tact,
             speed: 1,
             selected_state: Unexcitable,
+            input_bookmark_name: String::new(),
+            goto: (0, 0),
+            export_options: ImageOptions::default(),
+            animation_options: AnimationOptions::default(),
+
+            selection: None,
+            selection_start: None,
+
+            metadata: Metadata::default(),
 
             history: VecDeque::from([start]),
+
+            diff_reference: None,
+            differences: vec![],
+
+            script_source: String::new(),
+            script_output: vec![],
+
+            minimap: None,
+
+            rpc: None,
+
+            settings,
+            settings_changed: None,
+
+            heatmap: Heatmap::default(),
         }
     }
 
+    pub fn background(&self) -> Color {
+        draw::color(self.settings.theme.background)
+    }
+
+    pub fn listen(&mut self, receiver: Receiver<rpc::Request>) {
+        self.rpc = Some((receiver, rpc::Server::default()));
+    }
+
+    // Выполнение всех пришедших с прошлого кадра запросов
+    fn handle_rpc(&mut self) {
+        let Some((receiver, mut server)) = self.rpc.take() else {
+            return;
+        };
+
+        while let Ok(request) = receiver.try_recv() {
+            if let Some(response) = server.handle(self, &request.line) {
+                request.respond(&response);
+            }
+        }
+
+        self.rpc = Some((receiver, server));
+    }
+
     pub fn draw(&mut self, ctx: &CtxRef) {
+        self.handle_rpc();
+        self.save_settings_when_idle();
+
         // Обработка масштабирования
 
         if mouse_wheel() != (0.0, 0.0) {
             let (_, d) = mouse_wheel();
             let old_size = self.painter.cell_size.0;
 
+            // Масштаб меняется в разы, чтобы одинаково быстро отдалять и приближать
+            // как крупные, так и мелкие клетки
             let new_size = if d > 0.0 {
-                old_size + 5.0
+                old_size * 1.25
             } else {
-                old_size - 5.0
+                old_size / 1.25
             };
 
             self.painter.change_cell_size((new_size, new_size));
@@ -97,8 +212,78 @@ impl UI {
 
         self.painter.draw();
 
+        if self.f_show_heatmap {
+            self.painter.draw_heatmap(&self.heatmap);
+        }
+
+        // Отличия пересчитываются только после изменения поля
+        if let Some(reference) = &self.diff_reference {
+            if self.f_differences_outdated {
+                self.differences =
+                    cellular_automaton::diff(reference, &self.cellular_automation.read().unwrap());
+                self.f_differences_outdated = false;
+            }
+
+            self.painter.draw_differences(&self.differences);
+        }
+
+        if let Some(selection) = self.selection {
+            self.painter.draw_selection(selection);
+        }
+
+        if self.f_show_rulers {
+            self.painter.draw_rulers();
+        }
+
+        let left = if self.f_show_rulers {
+            draw::LEFT_RULER
+        } else {
+            0.0
+        };
+
+        self.minimap = if self.f_show_minimap {
+            self.painter.minimap(left)
+        } else {
+            None
+        };
+
+        if let Some(minimap) = self.minimap {
+            self.painter.draw_minimap(&minimap);
+
+            // Камера перемещается, пока левая кнопка, нажатая на мини-карте, не отпущена
+            if is_mouse_button_pressed(MouseButton::Left) && !ctx.is_pointer_over_area() {
+                self.f_minimap_drag = minimap.contains(mouse_position());
+            }
+
+            if self.f_minimap_drag {
+                self.painter.center_on(minimap.cell_at(mouse_position()));
+            }
+        }
+
+        if !is_mouse_button_down(MouseButton::Left) {
+            self.f_minimap_drag = false;
+        }
+
+        // Клетка под курсором мыши (если он не над окнами интерфейса, линейками или мини-картой)
+        let hovered = if ctx.is_pointer_over_area() || !self.over_field(mouse_position()) {
+            None
+        } else {
+            let coords = self.painter.cell_at(mouse_position());
+            let ca = self.cellular_automation.read().unwrap();
+
+            Some((coords, ca.try_get_cell(coords).copied().unwrap_or_default()))
+        };
+
         egui::SidePanel::right("GUI").show(ctx, |ui| {
             ui.heading("Клеточный автомат фон Неймана");
+
+            match hovered {
+                Some(((x, y), state)) => {
+                    ui.label(format!("Клетка ({}, {}): {}", x, y, state.notation()))
+                }
+                None => ui.label("Клетка: -"),
+            };
+
             ui.separator();
 
             if self.f_redact_field {
@@ -107,10 +292,45 @@ impl UI {
                 self.draw_main_page(ui);
             }
         });
+
+        let mut f_show_metadata = self.f_show_metadata;
+
+        egui::Window::new("Описание поля")
+            .open(&mut f_show_metadata)
+            .show(ctx, |ui| {
+                self.draw_metadata_window(ui);
+            });
+
+        self.f_show_metadata = f_show_metadata;
+
+        let mut f_show_console = self.f_show_console;
+
+        egui::Window::new("Консоль скриптов")
+            .open(&mut f_show_console)
+            .show(ctx, |ui| {
+                self.draw_console_window(ui);
+            });
+
+        self.f_show_console = f_show_console;
     }
 
     fn draw_main_page(&mut self, ui: &mut Ui) {
         ui.label("Для масштабирования использовать колёсико мыши или тачпад. \nДля перемещения поля зрения - стрелочки.");
+        ui.checkbox(
+            &mut self.f_show_minimap,
+            "Мини-карта (клик или перетаскивание по ней перемещает камеру)",
+        );
+        ui.checkbox(&mut self.f_show_rulers, "Линейки с координатами");
+
+        ui.horizontal(|ui| {
+            ui.label("Перейти к клетке");
+            ui.add(egui::DragValue::new(&mut self.goto.0).prefix("x: "));
+            ui.add(egui::DragValue::new(&mut self.goto.1).prefix("y: "));
+
+            if ui.button("Перейти").clicked() {
+                self.painter.center_on(self.goto);
+            }
+        });
 
         ui.collapsing("Дополнительная информация", |ui| {
             ui.label("Клеточный автомат с 29-ми состояниями.");
@@ -126,14 +346,8 @@ impl UI {
             if self.f_redact_tact {
                 ui.text_edit_singleline(&mut self.input_tact_text);
 
-                let input = self.input_tact_text.parse::<u64>();
-
-                match input {
-                    Ok(n) => {
-                        self.input_tact = n;
-                    }
-
-                    Err(_) => (),
+                if let Ok(n) = self.input_tact_text.parse::<u64>() {
+                    self.input_tact = n;
                 }
             } else {
                 ui.label(self.input_tact.to_string());
@@ -141,21 +355,30 @@ impl UI {
 
             if ui.button("R").clicked() {
                 if self.f_redact_tact {
-                    if self.input_tact > self.cellular_automation.read().unwrap().tact {
-                        while self.input_tact > self.cellular_automation.write().unwrap().tact {
-                            self.cellular_automation.write().unwrap().tact();
-                        }
+                    self.field_changed();
+
+                    let tact = self.cellular_automation.read().unwrap().tact;
+
+                    if self.input_tact > tact {
+                        self.run_tacts(self.input_tact - tact);
                     } else if self.input_tact < self.cellular_automation.read().unwrap().tact {
                         let mut dels = 0;
 
-                        for (i, ca) in self.history.iter().enumerate() {
-                            if ca.tact <= self.input_tact {
-                                let c = Rc::new(RwLock::new(ca.clone()));
+                        for (i, snapshot) in self.history.iter().enumerate() {
+                            if snapshot.tact() <= self.input_tact {
+                                match snapshot.restore() {
+                                    Ok(restored) => {
+                                        let c = Rc::new(RwLock::new(restored));
 
-                                self.painter.cellular_automaton = c.clone();
-                                self.cellular_automation = c;
+                                        self.painter.cellular_automaton = c.clone();
+                                        self.cellular_automation = c;
+                                        self.heatmap.reset();
+
+                                        dels = i;
+                                    }
+                                    Err(err) => show_error(&err),
+                                }
 
-                                dels = i;
                                 break;
                             }
                         }
@@ -164,9 +387,8 @@ impl UI {
                             self.history.pop_front();
                         }
 
-                        while self.input_tact > self.cellular_automation.write().unwrap().tact {
-                            self.cellular_automation.write().unwrap().tact();
-                        }
+                        let tact = self.cellular_automation.read().unwrap().tact;
+                        self.run_tacts(self.input_tact.saturating_sub(tact));
                     }
                 }
 
@@ -177,8 +399,7 @@ impl UI {
             }
 
             if !self.f_redact_tact && ui.button("Сделать шаг").clicked() {
-                self.input_tact += 1;
-                self.cellular_automation.write().unwrap().tact();
+                self.run_tacts(1);
             }
         });
 
@@ -235,95 +456,592 @@ impl UI {
         ui.separator();
 
         if ui.button("Загрузить поле").clicked() {
-            let path = FileDialog::new()
-                .set_location("~/")
-                .add_filter("data", &["data"])
+            let path = files::FILTERS
+                .iter()
+                .fold(
+                    FileDialog::new().set_location("~/"),
+                    |dialog, (name, extensions)| dialog.add_filter(name, extensions),
+                )
                 .show_open_single_file()
                 .unwrap();
 
-            match path {
+            match path.map(|path| files::load(&path)) {
                 None => {}
-                Some(path) => {
-                    let mut file = File::open(path.to_str().unwrap()).unwrap();
-                    let mut contents = String::new();
-                    file.read_to_string(&mut contents).unwrap();
-
-                    let deserialized: CellularAutomation = serde_json::from_str(&contents).unwrap();
-
-                    self.history = VecDeque::from([deserialized.clone()]);
-
-                    let tact = deserialized.tact;
-                    let ca = Rc::new(RwLock::new(deserialized));
-
-                    self.cellular_automation = ca.clone();
-                    self.painter = CellularAutomatonPainter::new(ca.clone(), (50.0, 50.0));
-                    self.input_tact = tact;
-                    self.input_tact_text = tact.to_string();
-                }
+                Some(Err(err)) => show_error(&err),
+                Some(Ok(document)) => self.open_document(document),
             };
         }
 
         if ui.button("Выгрузить поле").clicked() {
-            let path = FileDialog::new()
-                .set_location("~/")
-                .add_filter("data", &["data"])
+            let path = files::FILTERS
+                .iter()
+                .fold(
+                    FileDialog::new().set_location("~/"),
+                    |dialog, (name, extensions)| dialog.add_filter(name, extensions),
+                )
                 .show_save_single_file()
                 .unwrap();
 
-            match path {
-                None => {}
-                Some(path) => {
-                    let serialized =
-                        serde_json::to_string(&(*self.cellular_automation.read().unwrap().deref()))
-                            .unwrap();
+            if let Some(path) = path {
+                let metadata = Metadata {
+                    viewport: Some(Viewport {
+                        offset: self.painter.offset,
+                        cell_size: self.painter.cell_size,
+                    }),
+                    speed: Some(self.speed),
+                    ..self.metadata.clone()
+                };
+
+                if let Err(err) =
+                    files::save(&path, &self.cellular_automation.read().unwrap(), &metadata)
+                {
+                    show_error(&err);
+                }
+            }
+        }
+
+        if ui.button("Описание поля").clicked() {
+            self.f_show_metadata = !self.f_show_metadata;
+        }
 
-                    let mut file = File::create(path.to_str().unwrap()).unwrap();
+        if ui.button("Консоль скриптов").clicked() {
+            self.f_show_console = !self.f_show_console;
+        }
 
-                    file.write_all(serialized.as_bytes()).unwrap();
-                }
+        if self.diff_reference.is_none() {
+            if ui.button("Сравнить с полем из файла").clicked() {
+                let path = files::FILTERS
+                    .iter()
+                    .fold(
+                        FileDialog::new().set_location("~/"),
+                        |dialog, (name, extensions)| dialog.add_filter(name, extensions),
+                    )
+                    .show_open_single_file()
+                    .unwrap();
+
+                match path.map(|path| files::load(&path)) {
+                    None => {}
+                    Some(Err(err)) => show_error(&err),
+                    Some(Ok(document)) => {
+                        self.diff_reference = Some(document.automaton);
+                        self.f_differences_outdated = true;
+                    }
+                };
+            }
+        } else {
+            let count = |f: fn(&Difference) -> bool| {
+                self.differences
+                    .iter()
+                    .filter(|(_, difference)| f(difference))
+                    .count()
             };
+
+            ui.label(format!(
+                "Отличия от файла: добавлено {}, удалено {}, изменено {}",
+                count(|d| matches!(d, Difference::Added(_))),
+                count(|d| matches!(d, Difference::Removed(_))),
+                count(|d| matches!(d, Difference::Changed { .. })),
+            ));
+
+            if ui.button("Убрать сравнение").clicked() {
+                self.diff_reference = None;
+                self.differences.clear();
+            }
         }
 
+        ui.collapsing("Тепловая карта", |ui| {
+            self.draw_heatmap_settings(ui);
+        });
+
+        ui.collapsing("Оформление", |ui| {
+            self.draw_theme_settings(ui);
+        });
+
+        ui.collapsing("Экспорт изображения", |ui| {
+            ui.add(
+                egui::Slider::new(&mut self.export_options.cell_size, 1..=100)
+                    .text("Размер клетки (пикс.)"),
+            );
+
+            ui.checkbox(&mut self.export_options.grid, "Сетка");
+            ui.checkbox(
+                &mut self.export_options.labels,
+                "Подписи координат (только SVG)",
+            );
+
+            if self.selection.is_some() {
+                ui.checkbox(&mut self.f_export_selection, "Только выделенная область");
+            } else {
+                ui.label("Без выделения экспортируются все непустые клетки.");
+            }
+
+            let png = ui.button("Экспорт в PNG").clicked();
+            let svg = ui.button("Экспорт в SVG").clicked();
+
+            if png || svg {
+                let (name, extension) = if png { ("PNG", "png") } else { ("SVG", "svg") };
+
+                let path = FileDialog::new()
+                    .set_location("~/")
+                    .add_filter(name, &[extension])
+                    .show_save_single_file()
+                    .unwrap();
+
+                if let Some(path) = path {
+                    let options = ImageOptions {
+                        region: self.selection.filter(|_| self.f_export_selection),
+                        theme: self.settings.theme,
+                        ..self.export_options
+                    };
+
+                    let ca = self.cellular_automation.read().unwrap();
+
+                    let result = if png {
+                        export::export_png(&path, &ca, &options)
+                    } else {
+                        export::export_svg(&path, &ca, &options)
+                    };
+
+                    if let Err(err) = result {
+                        show_error(&err);
+                    }
+                }
+            }
+
+            ui.separator();
+            ui.label("Анимация (такты от и до, шаг, задержка в мс)");
+
+            ui.horizontal(|ui| {
+                ui.add(egui::DragValue::new(&mut self.animation_options.from));
+                ui.add(egui::DragValue::new(&mut self.animation_options.to));
+                ui.add(
+                    egui::DragValue::new(&mut self.animation_options.stride).clamp_range(1..=10000),
+                );
+                ui.add(
+                    egui::DragValue::new(&mut self.animation_options.delay).clamp_range(10..=10000),
+                );
+            });
+
+            let gif = ui.button("Экспорт в GIF").clicked();
+            let apng = ui.button("Экспорт в APNG").clicked();
+
+            if gif || apng {
+                let (name, extension) = if gif { ("GIF", "gif") } else { ("APNG", "png") };
+
+                let path = FileDialog::new()
+                    .set_location("~/")
+                    .add_filter(name, &[extension])
+                    .show_save_single_file()
+                    .unwrap();
+
+                if let Some(path) = path {
+                    let options = AnimationOptions {
+                        image: ImageOptions {
+                            region: self.selection.filter(|_| self.f_export_selection),
+                            theme: self.settings.theme,
+                            ..self.export_options
+                        },
+                        ..self.animation_options
+                    };
+
+                    let ca = self.cellular_automation.read().unwrap();
+
+                    let result = if gif {
+                        export::export_gif(&path, &ca, &options)
+                    } else {
+                        export::export_apng(&path, &ca, &options)
+                    };
+
+                    if let Err(err) = result {
+                        show_error(&err);
+                    }
+                }
+            }
+        });
+
         if !self.f_pause {
+            self.run_tacts(self.speed);
+        }
+    }
+
+    // Расчёт тактов с учётом активности клеток и сохранением истории каждые 200 тактов.
+    // Через эту функцию идут и такты из интерфейса, и запросы JSON-RPC.
+    fn run_tacts(&mut self, tacts: u64) {
+        let mut ca = self.cellular_automation.write().unwrap();
+
+        for _ in 0..tacts {
+            let activity = ca.tact_with_activity();
+
+            if self.f_show_heatmap {
+                self.heatmap.record(&activity);
+            }
+
+            if ca.tact.is_multiple_of(200) {
+                self.history.push_front(Snapshot::new(&ca));
+            }
+        }
+
+        self.input_tact = ca.tact;
+
+        drop(ca);
+        self.field_changed();
+    }
+
+    // Изменение клеток с сохранением поля в истории, чтобы к нему можно было вернуться
+    fn edit_cells(&mut self, cells: impl IntoIterator<Item = ((i64, i64), State)>) {
+        {
             let mut ca = self.cellular_automation.write().unwrap();
 
-            self.input_tact += self.speed;
+            for (coords, state) in cells {
+                ca.set_cell(coords, state);
+            }
+        }
 
-            for _ in 0..self.speed {
-                ca.tact();
+        self.history
+            .push_front(Snapshot::new(&self.cellular_automation.read().unwrap()));
+        self.field_changed();
+    }
 
-                if ca.tact % 200 == 0 {
-                    self.history.push_front(ca.clone());
-                }
+    fn draw_heatmap_settings(&mut self, ui: &mut Ui) {
+        ui.checkbox(&mut self.f_show_heatmap, "Показывать активность клеток");
+        ui.label("Учитываются изменения состояния и передача сигнала.");
+
+        ui.horizontal(|ui| {
+            ui.label("Ослабление вдвое через");
+            ui.add(egui::DragValue::new(&mut self.heatmap.half_life).clamp_range(0..=100000));
+            ui.label("тактов (0 - без затухания)");
+        });
+
+        // Легенда: шкала цветов от нуля до наибольшей активности
+        let max = self.heatmap.max();
+
+        ui.horizontal(|ui| {
+            ui.label("0");
+
+            let (rect, _) = ui.allocate_exact_size(egui::vec2(120.0, 12.0), egui::Sense::hover());
+            let steps = 24;
+
+            for i in 0..steps {
+                let [r, g, b, _]: [u8; 4] =
+                    draw::heat_color((i as f32 + 0.5) / steps as f32).into();
+                let width = rect.width() / steps as f32;
+                let left = rect.left() + width * i as f32;
+
+                ui.painter().rect_filled(
+                    egui::Rect::from_min_max(
+                        egui::pos2(left, rect.top()),
+                        egui::pos2(left + width, rect.bottom()),
+                    ),
+                    0.0,
+                    egui::Color32::from_rgb(r, g, b),
+                );
             }
+
+            ui.label(format!("{:.0}", max));
+        });
+
+        ui.label(format!("Учтено тактов: {}", self.heatmap.tacts));
+
+        if ui.button("Сбросить").clicked() {
+            self.heatmap.reset();
         }
     }
 
-    fn draw_redact_field_page(&mut self, ui: &mut Ui) {
-        // Обработка нажатия и изменения клетки
-        if is_mouse_button_pressed(MouseButton::Right) {
+    fn draw_theme_settings(&mut self, ui: &mut Ui) {
+        let before = self.settings.theme;
+
+        egui::ComboBox::from_label("Тема")
+            .selected_text(self.settings.theme.preset_name().unwrap_or("Своя"))
+            .show_ui(ui, |ui| {
+                for (name, theme) in Theme::PRESETS {
+                    ui.selectable_value(&mut self.settings.theme, theme, name);
+                }
+            });
+
+        let theme = &mut self.settings.theme;
+
+        for (name, rgb) in [
+            ("Фон", &mut theme.background),
+            ("Сетка", &mut theme.grid),
+            ("Чувствительные", &mut theme.sensitive),
+            ("Обычные передающие", &mut theme.normal),
+            ("Специальные передающие", &mut theme.special),
+            ("Точка специальных", &mut theme.special_mark),
+            ("Конфлюентные", &mut theme.confluence),
+            ("Сигнал", &mut theme.signal),
+        ] {
+            ui.horizontal(|ui| {
+                ui.color_edit_button_srgb(rgb);
+                ui.label(name);
+            });
+        }
+
+        // Тема применяется сразу, а сохраняется после того, как её перестали менять
+        if self.settings.theme != before {
+            self.painter.theme = self.settings.theme;
+            self.settings_changed = Some(Instant::now());
+        }
+    }
+
+    // Пока цвет перетаскивают в палитре, тема меняется каждый кадр, поэтому файл
+    // настроек записывается, только когда кнопка мыши отпущена и изменений не было секунду
+    fn save_settings_when_idle(&mut self) {
+        let Some(changed) = self.settings_changed else {
+            return;
+        };
+
+        if changed.elapsed() < Duration::from_secs(1) || is_mouse_button_down(MouseButton::Left) {
+            return;
+        }
+
+        self.settings_changed = None;
+
+        if let Err(err) = self.settings.save() {
+            show_error(&err);
+        }
+    }
+
+    pub fn open_document(&mut self, document: Document) {
+        let Document {
+            automaton,
+            metadata,
+        } = document;
+
+        self.history = VecDeque::from([Snapshot::new(&automaton)]);
+
+        let tact = automaton.tact;
+        let ca = Rc::new(RwLock::new(automaton));
+
+        self.cellular_automation = ca.clone();
+        self.painter = CellularAutomatonPainter::new(ca, (50.0, 50.0));
+        self.painter.theme = self.settings.theme;
+        self.heatmap.reset();
+        self.input_tact = tact;
+        self.input_tact_text = tact.to_string();
+
+        // Нулевой, бесконечный или слишком мелкий масштаб из повреждённого файла
+        // повесил бы отрисовку, поэтому такая камера не восстанавливается
+        let valid = |(w, h): (f32, f32)| {
+            w.is_finite() && h.is_finite() && w >= draw::MIN_CELL_SIZE && h >= draw::MIN_CELL_SIZE
+        };
+
+        if let Some(viewport) = metadata.viewport {
+            if valid(viewport.cell_size)
+                && viewport.offset.0.is_finite()
+                && viewport.offset.1.is_finite()
             {
-                let (x, y) = mouse_position(); // Получение сырой позиции
+                self.painter.offset = viewport.offset;
+                self.painter.cell_size = viewport.cell_size;
+            }
+        }
 
-                // Применение смещения
-                let (x, y) = (x - self.painter.offset.0, y - self.painter.offset.1);
+        if let Some(speed) = metadata.speed {
+            self.speed = speed;
+        }
 
-                // Приведение к координатам ячеек
-                let (x, y) = (
-                    (x / self.painter.cell_size.0).ceil() as i64 - 1,
-                    (y / self.painter.cell_size.1).ceil() as i64 - 1,
-                );
+        self.metadata = metadata;
+        self.f_show_metadata = true;
+
+        self.field_changed();
+    }
+
+    // Замена поля из скрипта или по запросу JSON-RPC с сохранением в истории
+    fn replace_automaton(&mut self, automaton: CellularAutomation) {
+        // Если поле вернулось в прошлое (например, загружен файл), старая история
+        // больше не подходит
+        if automaton.tact < self.cellular_automation.read().unwrap().tact {
+            self.history.clear();
+        }
+
+        self.history.push_front(Snapshot::new(&automaton));
+
+        self.input_tact = automaton.tact;
+        self.input_tact_text = self.input_tact.to_string();
+
+        *self.cellular_automation.write().unwrap() = automaton;
+
+        // Накопленная активность относится к прежнему полю
+        self.heatmap.reset();
+        self.field_changed();
+    }
+
+    // Точка экрана лежит над самим полем, а не над линейками или мини-картой
+    fn over_field(&self, (x, y): (f32, f32)) -> bool {
+        let over_rulers = self.f_show_rulers && (x < draw::LEFT_RULER || y < draw::TOP_RULER);
+        let over_minimap = self.minimap.is_some_and(|minimap| minimap.contains((x, y)));
+
+        !over_rulers && !over_minimap
+    }
+
+    // Вызывается после любого изменения клеток поля: такта, правки, замены или возврата к истории
+    fn field_changed(&mut self) {
+        self.f_differences_outdated = true;
+        self.painter.field_changed();
+    }
+
+    fn draw_console_window(&mut self, ui: &mut Ui) {
+        ui.label("Скрипт на Rhai. Функции: get_cell, set_cell, fill, tact, selection, select, load, save, center_on, zoom и другие.");
 
-                let mut ca = self.cellular_automation.write().unwrap();
+        ui.add(
+            egui::TextEdit::multiline(&mut self.script_source)
+                .code_editor()
+                .desired_rows(10),
+        );
 
-                ca.set_cell((x, y), self.selected_state);
+        ui.horizontal(|ui| {
+            if ui.button("Выполнить").clicked() {
+                self.run_script();
             }
 
-            self.history
-                .push_front((*self.cellular_automation.read().unwrap().deref()).clone());
+            if ui.button("Очистить вывод").clicked() {
+                self.script_output.clear();
+            }
+        });
+
+        ui.separator();
+
+        egui::ScrollArea::vertical()
+            .max_height(200.0)
+            .stick_to_bottom()
+            .show(ui, |ui| {
+                for line in &self.script_output {
+                    ui.monospace(line);
+                }
+            });
+    }
+
+    fn run_script(&mut self) {
+        let mut script = Script::new(
+            self.cellular_automation.read().unwrap().clone(),
+            self.metadata.clone(),
+        );
+
+        script.selection = self.selection;
+        script.camera = self.painter.center_cell();
+        script.cell_size = self.painter.cell_size.0;
+
+        let (camera, cell_size) = (script.camera, script.cell_size);
+
+        let (script, result) = script::run(&self.script_source, script);
+
+        self.script_output.extend(script.output);
+
+        if let Err(err) = result {
+            self.script_output.push(format!("Ошибка: {}", err));
+        }
+
+        self.replace_automaton(script.automaton);
+
+        self.metadata = script.metadata;
+        self.selection = script.selection;
+
+        if script.cell_size != cell_size {
+            self.painter
+                .change_cell_size((script.cell_size, script.cell_size));
+        }
+
+        if script.camera != camera || script.cell_size != cell_size {
+            self.painter.center_on(script.camera);
+        }
+    }
+
+    fn draw_metadata_window(&mut self, ui: &mut Ui) {
+        ui.horizontal(|ui| {
+            ui.label("Название: ");
+            ui.text_edit_singleline(&mut self.metadata.title);
+        });
+
+        ui.horizontal(|ui| {
+            ui.label("Автор: ");
+            ui.text_edit_singleline(&mut self.metadata.author);
+        });
+
+        ui.label(format!("Правило: {}", self.metadata.rule));
+
+        ui.label("Описание: ");
+        ui.text_edit_multiline(&mut self.metadata.description);
+
+        ui.label("Заметки: ");
+        ui.text_edit_multiline(&mut self.metadata.notes);
+
+        ui.separator();
+
+        ui.label("Закладки: ");
+
+        let mut go_to = None;
+        let mut remove = None;
+
+        for (i, bookmark) in self.metadata.bookmarks.iter().enumerate() {
+            ui.horizontal(|ui| {
+                ui.label(format!(
+                    "{} ({}, {})",
+                    bookmark.name, bookmark.coords.0, bookmark.coords.1
+                ));
+
+                if ui.button("Перейти").clicked() {
+                    go_to = Some(bookmark.coords);
+                }
+
+                if ui.button("X").clicked() {
+                    remove = Some(i);
+                }
+            });
+        }
+
+        if let Some(coords) = go_to {
+            self.painter.center_on(coords);
+        }
+
+        if let Some(i) = remove {
+            self.metadata.bookmarks.remove(i);
+        }
+
+        ui.horizontal(|ui| {
+            ui.text_edit_singleline(&mut self.input_bookmark_name);
+
+            if ui.button("Добавить закладку").clicked() {
+                self.metadata.bookmarks.push(Bookmark {
+                    name: self.input_bookmark_name.clone(),
+                    coords: self.painter.center_cell(),
+                });
+
+                self.input_bookmark_name.clear();
+            }
+        });
+
+        ui.label("Закладка ставится на клетку в центре экрана. Описание и закладки сохраняются вместе с полем.");
+    }
+
+    fn draw_redact_field_page(&mut self, ui: &mut Ui) {
+        // Обработка нажатия и изменения клетки (кроме нажатий по линейкам и мини-карте)
+        if is_mouse_button_pressed(MouseButton::Right) && self.over_field(mouse_position()) {
+            let (x, y) = self.painter.cell_at(mouse_position());
+
+            if is_key_down(KeyCode::LeftShift) || is_key_down(KeyCode::RightShift) {
+                self.selection_start = Some((x, y));
+            } else {
+                self.edit_cells([((x, y), self.selected_state)]);
+            }
+        }
+
+        // Обработка выделения области
+        if let Some((sx, sy)) = self.selection_start {
+            if is_mouse_button_down(MouseButton::Right) {
+                let (x, y) = self.painter.cell_at(mouse_position());
+
+                self.selection = Some(((sx.min(x), sy.min(y)), (sx.max(x), sy.max(y))));
+            } else {
+                self.selection_start = None;
+            }
         }
 
         ui.label("Для изменения ячейки кликните по ней правой кнопкой мыши");
+        ui.label("Для выделения области зажмите Shift и протяните правой кнопкой мыши");
+
+        if self.selection.is_some() && ui.button("Снять выделение").clicked() {
+            self.selection = None;
+        }
+
         ui.separator();
 
         ui.checkbox(
@@ -454,3 +1172,59 @@ impl UI {
         }
     }
 }
+
+fn show_error(err: &impl Display) {
+    MessageDialog::new()
+        .set_type(MessageType::Error)
+        .set_title("Ошибка")
+        .set_text(&err.to_string())
+        .show_alert()
+        .unwrap();
+}
+
+impl Simulator for UI {
+    fn automaton(&self) -> impl Deref<Target = CellularAutomation> + '_ {
+        self.cellular_automation.read().unwrap()
+    }
+
+    fn automaton_mut<R>(&mut self, f: impl FnOnce(&mut CellularAutomation) -> R) -> R {
+        let result = f(&mut self.cellular_automation.write().unwrap());
+
+        self.field_changed();
+        self.input_tact = self.cellular_automation.read().unwrap().tact;
+        self.input_tact_text = self.input_tact.to_string();
+
+        result
+    }
+
+    fn metadata(&self) -> Metadata {
+        self.metadata.clone()
+    }
+
+    fn step(&mut self, tacts: u64) -> u64 {
+        self.run_tacts(tacts);
+        self.input_tact_text = self.input_tact.to_string();
+
+        self.input_tact
+    }
+
+    fn set_cells(&mut self, cells: Vec<((i64, i64), State)>) {
+        self.edit_cells(cells);
+    }
+
+    fn replace(&mut self, automaton: CellularAutomation, metadata: Option<Metadata>) {
+        self.replace_automaton(automaton);
+
+        if let Some(metadata) = metadata {
+            self.metadata = metadata;
+        }
+    }
+
+    fn paused(&self) -> bool {
+        self.f_pause
+    }
+
+    fn set_paused(&mut self, paused: bool) {
+        self.f_pause = paused;
+    }
+}